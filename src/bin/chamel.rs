@@ -6,6 +6,8 @@ use std::os::unix::net::UnixStream;
 use clap::Parser;
 
 use chameleos::Command;
+use chameleos::Message;
+use chameleos::Target;
 
 mod metadata {
     include!(concat!(env!("OUT_DIR"), "/metadata.rs"));
@@ -20,6 +22,14 @@ mod metadata {
     long_about = None,
 )]
 struct Cli {
+    /// Output to apply the command to, by connector name (e.g. `DP-1`) or part of its
+    /// description like `chameleos --output`, or `all`
+    ///
+    /// Without this, undo and redo apply to the output that was last drawn on and everything
+    /// else to all outputs.
+    #[arg(short, long, global = true)]
+    output: Option<Target>,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -30,6 +40,10 @@ fn main() -> std::io::Result<()> {
     let socket_addr = SocketAddr::from_abstract_name("chameleos.sock")?;
     let mut stream = UnixStream::connect_addr(&socket_addr)?;
 
-    let s = cli.command.serialize();
+    let message = Message {
+        target: cli.output,
+        command: cli.command,
+    };
+    let s = message.serialize();
     stream.write_all(&s)
 }
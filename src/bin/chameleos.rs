@@ -1,4 +1,5 @@
 use std::io::Read;
+use std::time::Instant;
 
 use std::os::linux::net::SocketAddrExt;
 use std::os::unix::net::SocketAddr;
@@ -12,7 +13,9 @@ use log::log;
 
 use clap::Parser;
 
-use chameleos::Command;
+use wayland_client::EventQueue;
+
+use chameleos::Message;
 
 const EPSILON: f32 = 5.0;
 
@@ -40,6 +43,132 @@ struct Cli {
 
     #[arg(short = 'b', long)]
     force_backend: Option<render::Backend>,
+
+    /// Output to show an overlay on, by connector name (e.g. `DP-1`) or part of its description
+    ///
+    /// Can be given multiple times. Without this, every output gets an overlay.
+    #[arg(short = 'o', long)]
+    output: Vec<String>,
+
+    /// Stretch the lines along when an output changes its size (e.g. a new resolution or a
+    /// rotation), instead of keeping them where they are
+    #[arg(long)]
+    rescale_on_resize: bool,
+
+    /// Map a pointer or stylus button to a tool, e.g. `--bind middle=eraser`
+    ///
+    /// Can be given multiple times. Buttons are named (left, right, middle, side, extra, tip,
+    /// stylus, stylus2, stylus3) or given as evdev codes. The tool can come with its own stroke
+    /// settings as [TOOL][:WIDTH[:COLOR]], e.g. `--bind side=pen:8:red`. Defaults are left=pen,
+    /// right=eraser, tip=pen and stylus=eraser.
+    #[arg(long, value_name = "BUTTON=PRESET")]
+    bind: Vec<chameleos::Binding>,
+
+    /// Use a tool and stroke settings for a type of tablet tool, e.g. `--tablet-tool pencil=pen:4`
+    ///
+    /// Can be given multiple times. Presets are written as TOOL[:WIDTH[:COLOR]], settings that
+    /// are left out use the current stroke width and color. Types are pen, eraser, brush,
+    /// pencil, airbrush, finger, mouse and lens. By default the eraser end of a pen erases.
+    #[arg(long, value_name = "TYPE=PRESET")]
+    tablet_tool: Vec<chameleos::TabletToolBinding>,
+
+    /// Use a tool and stroke settings for a physical stylus, e.g. `--stylus 0x1a2b3c=pen::red`
+    ///
+    /// Can be given multiple times. Styluses are identified by their hardware serial, which gets
+    /// logged under `chameleos::general` when the tablet reports the stylus. Takes precedence over
+    /// `--tablet-tool`.
+    #[arg(long, value_name = "SERIAL=PRESET")]
+    stylus: Vec<chameleos::StylusBinding>,
+
+    /// Ignore touches with a contact larger than this (in pixels) as well as all touches while a
+    /// pen is in proximity
+    #[arg(long, value_name = "SIZE")]
+    palm_rejection: Option<f64>,
+
+    /// Colors to cycle through with `chamel next-color` and `chamel previous-color`
+    #[arg(
+        long,
+        num_args = 1..,
+        default_values = ["red", "deepskyblue", "limegreen", "yellow", "white", "black"],
+    )]
+    palette: Vec<csscolorparser::Color>,
+
+    /// Run a command when a tablet pad button is pressed, e.g. `--pad-button 0=undo`
+    ///
+    /// Can be given multiple times. Pad buttons are numbered from 0, commands are written the
+    /// way they are sent over the socket, e.g. `toggle`, `clear`, `next_color` or
+    /// `"stroke_width 16"`.
+    #[arg(long, value_name = "BUTTON=COMMAND")]
+    pad_button: Vec<chameleos::PadButtonBinding>,
+
+    /// What turning a tablet pad ring does
+    #[arg(long, default_value = "width")]
+    pad_ring: state::PadAxis,
+
+    /// What sliding along a tablet pad strip does
+    #[arg(long, default_value = "color")]
+    pad_strip: state::PadAxis,
+
+    /// While this button is held, the scroll wheel cycles through the palette instead of
+    /// changing the stroke width
+    #[arg(long, value_name = "BUTTON", default_value = "middle")]
+    scroll_modifier: chameleos::Button,
+
+    /// Run a command on a touchpad gesture while drawing, e.g. `--gesture pinch-out:2=clear`
+    ///
+    /// Can be given multiple times. Gestures are swipe-left, swipe-right, swipe-up, swipe-down,
+    /// pinch-in, pinch-out and hold, optionally followed by a finger count, e.g. `swipe-up:4`.
+    /// Commands are written the way they are sent over the socket. Defaults are
+    /// swipe-left:3=undo and swipe-right:3=redo.
+    #[arg(long, value_name = "GESTURE=COMMAND")]
+    gesture: Vec<chameleos::GestureBinding>,
+
+    /// Show a toolbar docked to this screen edge while input is active
+    ///
+    /// Without this the toolbar starts hidden, `chamel toolbar` shows it. Picking the pen or
+    /// eraser on it binds that tool to both the left mouse button and the pen tip.
+    #[arg(long, value_enum, value_name = "EDGE")]
+    toolbar: Option<chameleos::Edge>,
+
+    /// How overlays show that they take input, in the current stroke color
+    #[arg(long, value_enum, default_value_t = state::IndicatorKind::None)]
+    indicator: state::IndicatorKind,
+
+    /// What the cursor looks like over the overlays
+    ///
+    /// `brush` hides the system cursor and draws a dot of the stroke width and color in its
+    /// place, or a ring the size of the eraser while erasing.
+    #[arg(long, value_enum, default_value_t = state::CursorStyle::Crosshair)]
+    cursor: state::CursorStyle,
+
+    /// Open a radial menu of tools, widths and colors while this button is held, e.g.
+    /// `--radial-menu stylus2`
+    ///
+    /// Moving towards a slice and letting go of the button picks it. Takes precedence over a tool
+    /// mapped to the same button.
+    #[arg(long, value_name = "BUTTON")]
+    radial_menu: Option<chameleos::Button>,
+
+    /// Go back to passing input through after this many seconds without drawing
+    ///
+    /// `chamel activate --timeout` overrides this for one activation.
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<f32>,
+
+    /// Whether overlays take keyboard focus while input is active, which the shortcuts need
+    ///
+    /// Layer shell before version 4 only knows exclusive focus, which on-demand falls back to.
+    #[arg(long, value_enum, default_value_t = state::KeyboardFocus::OnDemand)]
+    keyboard: state::KeyboardFocus,
+
+    /// Run a command on a shortcut while drawing, e.g. `--key ctrl+shift+c=clear`
+    ///
+    /// Can be given multiple times. Keys are xkb keysym names (e.g. `z`, `Escape` or `F5`) with
+    /// any of the modifiers ctrl, shift, alt and super. Commands are written the way they are sent
+    /// over the socket. Defaults are ctrl+z=undo, ctrl+shift+z=redo, Escape=deactivate and the number
+    /// keys 1 to 9 for the colors of the palette.
+    #[arg(long, value_name = "SHORTCUT=COMMAND")]
+    key: Vec<state::ShortcutBinding>,
 }
 
 fn main() {
@@ -60,11 +189,13 @@ fn main() {
     };
     let mut listener_buffer: Vec<u8> = Vec::with_capacity(128);
 
-    let (mut state, mut event_queue) = state::State::setup_wayland(cli);
-    let qhandle = event_queue.handle();
-
-    state.deactivate(&qhandle);
-
+    let (mut state, mut event_queue) = match state::State::setup_wayland(cli) {
+        Ok(setup) => setup,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
     let (sender, receiver) = std::sync::mpsc::channel();
 
     std::thread::spawn(move || {
@@ -78,8 +209,8 @@ fn main() {
                 String::from_utf8_lossy(&listener_buffer)
             );
 
-            match Command::deserialize(&listener_buffer) {
-                Ok(command) => sender.send(command).unwrap(),
+            match Message::deserialize(&listener_buffer) {
+                Ok(message) => sender.send(message).unwrap(),
                 Err(s) => eprintln!("{}", s),
             }
             listener_buffer.clear();
@@ -87,24 +218,61 @@ fn main() {
     });
 
     let qhandle = event_queue.handle();
-    loop {
-        event_queue.blocking_dispatch(&mut state).unwrap();
-
-        if let Ok(command) = receiver.try_recv() {
-            match command {
-                Command::Toggle => state.toggle_input(&qhandle),
-                Command::Undo => state.undo(),
-                Command::Clear => state.clear(),
-                Command::ClearAndDeactivate => {
-                    state.clear();
-                    state.deactivate(&event_queue.handle());
-                }
-                Command::StrokeWidth { width } => state.set_stroke_width(width),
-                Command::StrokeColor { color } => state.set_stroke_color(color),
-                Command::Exit => break,
-            }
+    while !state.should_exit() {
+        let deadline = state.next_deadline();
+        dispatch_until(&mut event_queue, &mut state, deadline);
+
+        if let Ok(message) = receiver.try_recv() {
+            state.execute_on(message.target.as_ref(), message.command, &qhandle);
         }
+
+        state.expire_activations(&qhandle);
     }
 
     println!("Exiting");
 }
+
+/// Like `EventQueue::blocking_dispatch`, but stops waiting for events at the deadline
+fn dispatch_until(
+    event_queue: &mut EventQueue<state::State>,
+    state: &mut state::State,
+    deadline: Option<Instant>,
+) {
+    use rustix::event::PollFd;
+    use rustix::event::PollFlags;
+    use rustix::event::Timespec;
+
+    if event_queue.dispatch_pending(state).unwrap() > 0 {
+        return;
+    }
+
+    event_queue.flush().unwrap();
+
+    if let Some(guard) = event_queue.prepare_read() {
+        let timeout = deadline.map(|deadline| {
+            Timespec::try_from(deadline.saturating_duration_since(Instant::now())).unwrap()
+        });
+
+        let fd = guard.connection_fd();
+        let mut fds = [PollFd::new(&fd, PollFlags::IN | PollFlags::ERR)];
+        let ready = loop {
+            match rustix::event::poll(&mut fds, timeout.as_ref()) {
+                Ok(ready) => break ready,
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(e) => panic!("{e}"),
+            }
+        };
+
+        // without events the guard is dropped, which cancels the read
+        if ready > 0 {
+            match guard.read() {
+                Ok(_) => {}
+                Err(wayland_client::backend::WaylandError::Io(e))
+                    if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => panic!("{e}"),
+            }
+        }
+    }
+
+    event_queue.dispatch_pending(state).unwrap();
+}
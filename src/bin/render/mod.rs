@@ -9,6 +9,7 @@ use lyon::tessellation::VertexBuffers;
 
 use wayland_client::Proxy;
 use wayland_client::protocol::wl_display::WlDisplay;
+use wayland_client::protocol::wl_output::Transform;
 use wayland_client::protocol::wl_surface::WlSurface;
 
 use wgpu::util::DeviceExt;
@@ -47,6 +48,17 @@ impl Geometry {
     }
 }
 
+/// How a surface is laid out in its buffer
+#[derive(Clone, Copy, PartialEq)]
+pub struct SurfaceSize {
+    /// in physical pixels, after the transform
+    pub buffer: (u32, u32),
+    /// the logical size lines are drawn in
+    pub screen: (u32, u32),
+    /// what was already applied to the buffer, so the compositor doesn't have to
+    pub transform: Transform,
+}
+
 #[allow(unused)]
 pub struct WgpuState {
     surface: wgpu::Surface<'static>,
@@ -74,10 +86,11 @@ impl WgpuState {
     pub fn new(
         display: &WlDisplay,
         surface: &WlSurface,
-        width: u32,
-        height: u32,
+        size: SurfaceSize,
         force_backend: Option<Backend>,
     ) -> Self {
+        let (width, height) = size.buffer;
+
         let wgpu_instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: match force_backend {
                 Some(Backend::Vulkan) => wgpu::Backends::VULKAN,
@@ -100,8 +113,8 @@ impl WgpuState {
 
         let wgpu_surface = unsafe {
             wgpu_instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::RawHandle {
-                raw_display_handle: raw_display_handle,
-                raw_window_handle: raw_window_handle,
+                raw_display_handle,
+                raw_window_handle,
             })
         }
         .unwrap();
@@ -174,32 +187,16 @@ impl WgpuState {
 
         wgpu_surface.configure(&wgpu_device, &wgpu_config);
 
-        let multisampled_texture = wgpu_device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
-            size: wgpu::Extent3d {
-                width: width as u32,
-                height: height as u32,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: SAMPLE_COUNT,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu_config.format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        });
-        let multisampled_texture_view =
-            multisampled_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (multisampled_texture, multisampled_texture_view) =
+            create_multisampled_texture(&wgpu_device, &wgpu_config);
 
         // =====
 
-        let uniform = Uniform {
-            screen_size: [width as f32, height as f32],
-        };
+        let uniform = Uniform::new(size.screen, size.transform);
         let uniform_buffer = wgpu_device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
             contents: bytemuck::bytes_of(&uniform),
-            usage: wgpu::BufferUsages::UNIFORM,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
         let uniform_bind_group_layout =
@@ -307,6 +304,20 @@ impl WgpuState {
         }
     }
 
+    /// Reconfigures everything that depends on the size of the surface
+    pub fn resize(&mut self, size: SurfaceSize) {
+        self.surface_config.width = size.buffer.0;
+        self.surface_config.height = size.buffer.1;
+        self.surface.configure(&self.device, &self.surface_config);
+
+        (self.multisampled_texture, self.multisampled_texture_view) =
+            create_multisampled_texture(&self.device, &self.surface_config);
+
+        let uniform = Uniform::new(size.screen, size.transform);
+        self.queue
+            .write_buffer(&self.screen_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
     pub fn render<'a>(&self, geometries: impl IntoIterator<Item = &'a Geometry>) {
         let output = match self.surface.get_current_texture() {
             Ok(output) => output,
@@ -383,3 +394,25 @@ impl WgpuState {
         output.present();
     }
 }
+
+fn create_multisampled_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: SAMPLE_COUNT,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
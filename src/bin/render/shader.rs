@@ -1,7 +1,37 @@
+use wayland_client::protocol::wl_output::Transform;
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Uniform {
     pub screen_size: [f32; 2],
+    /// turns the screen into the buffer, column by column
+    pub transform: [[f32; 2]; 2],
+}
+
+impl Uniform {
+    pub fn new(screen_size: (u32, u32), transform: Transform) -> Self {
+        // the compositor turns the buffer counter-clockwise to show it, so it is drawn turned
+        // clockwise here, flips happen around the vertical axis before rotating
+        let (rotation, flipped) = match transform {
+            Transform::Normal => ([[1.0, 0.0], [0.0, 1.0]], false),
+            Transform::_90 => ([[0.0, -1.0], [1.0, 0.0]], false),
+            Transform::_180 => ([[-1.0, 0.0], [0.0, -1.0]], false),
+            Transform::_270 => ([[0.0, 1.0], [-1.0, 0.0]], false),
+            Transform::Flipped => ([[1.0, 0.0], [0.0, 1.0]], true),
+            Transform::Flipped90 => ([[0.0, -1.0], [1.0, 0.0]], true),
+            Transform::Flipped180 => ([[-1.0, 0.0], [0.0, -1.0]], true),
+            Transform::Flipped270 => ([[0.0, 1.0], [-1.0, 0.0]], true),
+            _ => ([[1.0, 0.0], [0.0, 1.0]], false),
+        };
+
+        let [x, y]: [[f32; 2]; 2] = rotation;
+        let x = if flipped { [-x[0], -x[1]] } else { x };
+
+        Self {
+            screen_size: [screen_size.0 as f32, screen_size.1 as f32],
+            transform: [x, y],
+        }
+    }
 }
 
 #[repr(C)]
@@ -12,9 +42,9 @@ pub struct Vertex {
 }
 
 impl Vertex {
-    pub fn new(vertex: lyon::tessellation::StrokeVertex, color: &csscolorparser::Color) -> Self {
+    pub fn new(position: lyon::math::Point, color: &csscolorparser::Color) -> Self {
         Self {
-            position: vertex.position().to_array(),
+            position: position.to_array(),
             color: color.to_array(),
         }
     }
@@ -28,3 +58,77 @@ impl Vertex {
         ],
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: f32 = 400.0;
+    const HEIGHT: f32 = 300.0;
+
+    /// Where a point of the surface, y going down, ends up in the buffer, the way the shader
+    /// maps it
+    fn to_buffer(transform: Transform, (x, y): (f32, f32)) -> (f32, f32) {
+        let uniform = Uniform::new((WIDTH as u32, HEIGHT as u32), transform);
+        let [column_x, column_y] = uniform.transform;
+
+        // lines go up from the bottom
+        let ndc = (x * 2.0 / WIDTH - 1.0, (HEIGHT - y) * 2.0 / HEIGHT - 1.0);
+        let ndc = (
+            column_x[0] * ndc.0 + column_y[0] * ndc.1,
+            column_x[1] * ndc.0 + column_y[1] * ndc.1,
+        );
+
+        let sideways = matches!(
+            transform,
+            Transform::_90 | Transform::_270 | Transform::Flipped90 | Transform::Flipped270
+        );
+        let (buffer_width, buffer_height) = if sideways {
+            (HEIGHT, WIDTH)
+        } else {
+            (WIDTH, HEIGHT)
+        };
+        (
+            (ndc.0 + 1.0) / 2.0 * buffer_width,
+            (1.0 - ndc.1) / 2.0 * buffer_height,
+        )
+    }
+
+    /// How wl_surface.set_buffer_transform relates surface and buffer coordinates
+    fn expected(transform: Transform, (x, y): (f32, f32)) -> (f32, f32) {
+        match transform {
+            Transform::Normal => (x, y),
+            Transform::_90 => (HEIGHT - y, x),
+            Transform::_180 => (WIDTH - x, HEIGHT - y),
+            Transform::_270 => (y, WIDTH - x),
+            Transform::Flipped => (WIDTH - x, y),
+            Transform::Flipped90 => (HEIGHT - y, WIDTH - x),
+            Transform::Flipped180 => (x, HEIGHT - y),
+            Transform::Flipped270 => (y, x),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn corners_land_where_the_compositor_expects_them() {
+        let corners = [(0.0, 0.0), (WIDTH, 0.0), (0.0, HEIGHT), (WIDTH, HEIGHT)];
+        for transform in [
+            Transform::Normal,
+            Transform::_90,
+            Transform::_180,
+            Transform::_270,
+            Transform::Flipped,
+            Transform::Flipped90,
+            Transform::Flipped180,
+            Transform::Flipped270,
+        ] {
+            for corner in corners {
+                assert_eq!(
+                    to_buffer(transform, corner),
+                    expected(transform, corner),
+                    "{transform:?} at {corner:?}"
+                );
+            }
+        }
+    }
+}
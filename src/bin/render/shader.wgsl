@@ -1,5 +1,6 @@
 struct Uniform {
     screen_size: vec2<f32>,
+    transform: mat2x2<f32>,
 };
 
 @group(0) @binding(0)
@@ -18,11 +19,11 @@ struct VertexOutput {
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
-    out.pos = vec4<f32>(
+    let pos = info.transform * vec2<f32>(
         (in.pos.x * 2.0) / info.screen_size.x - 1.0,
         (in.pos.y * 2.0) / info.screen_size.y - 1.0,
-        0.0, 1.0,
     );
+    out.pos = vec4<f32>(pos, 0.0, 1.0);
     out.color = in.color;
     return out;
 }
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use chameleos::Binding;
+use chameleos::Button;
+use chameleos::Preset;
+use chameleos::Tool;
+
+use super::draw::Stroke;
+
+/// The tool and stroke settings each button draws with
+pub struct ButtonMap {
+    map: HashMap<Button, Preset>,
+}
+
+impl Default for ButtonMap {
+    fn default() -> Self {
+        let tool = |tool| Preset {
+            tool: Some(tool),
+            ..Default::default()
+        };
+        Self {
+            map: HashMap::from([
+                (Button::LEFT, tool(Tool::Pen)),
+                (Button::RIGHT, tool(Tool::Eraser)),
+                (Button::TIP, tool(Tool::Pen)),
+                (Button::STYLUS, tool(Tool::Eraser)),
+            ]),
+        }
+    }
+}
+
+impl ButtonMap {
+    pub fn new(bindings: &[Binding]) -> Self {
+        let mut map = Self::default();
+        for binding in bindings {
+            map.bind(binding.button, &binding.preset);
+        }
+        map
+    }
+
+    /// Presets that leave out the tool draw with the pen
+    pub fn bind(&mut self, button: Button, preset: &Preset) {
+        let preset = Preset {
+            tool: Some(preset.tool.unwrap_or(Tool::Pen)),
+            ..preset.clone()
+        };
+        self.map.insert(button, preset);
+    }
+
+    pub fn get(&self, button: u32) -> Option<Tool> {
+        self.preset(button).and_then(|preset| preset.tool)
+    }
+
+    pub fn preset(&self, button: u32) -> Option<&Preset> {
+        self.map.get(&Button(button))
+    }
+
+    /// The stroke a button draws with, after the settings its binding brings along
+    pub fn stroke(&self, button: u32, stroke: &Stroke) -> Stroke {
+        match self.preset(button) {
+            Some(preset) => stroke.with_preset(preset),
+            None => stroke.clone(),
+        }
+    }
+}
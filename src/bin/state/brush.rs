@@ -0,0 +1,99 @@
+use lyon::math::Point;
+use lyon::math::point;
+use lyon::path::Path;
+
+use chameleos::Tool;
+
+use super::draw::DrawState;
+use super::draw::Stroke;
+use super::input::Device;
+use super::ui::Painter;
+
+use crate::render::Geometry;
+
+/// Width of the outline that keeps the brush visible on any background
+const OUTLINE_WIDTH: f32 = 1.5;
+/// Dots of thinner strokes are drawn this large, so they can still be found
+const MIN_RADIUS: f32 = 2.0;
+/// Size of the dot pointing at buttons and slices
+const POINTER_RADIUS: f32 = 3.0;
+
+const LIGHT: csscolorparser::Color = csscolorparser::Color::new(1.0, 1.0, 1.0, 0.9);
+const DARK: csscolorparser::Color = csscolorparser::Color::new(0.0, 0.0, 0.0, 0.6);
+
+/// Drawn in place of the system cursor where a hovering pointer or pen is, shows what the tool
+/// is about to draw or erase
+pub struct Brush {
+    device: Device,
+    geometry: Geometry,
+}
+
+impl Brush {
+    pub fn new(
+        device: Device,
+        (x, y): (f64, f64),
+        tool: Tool,
+        stroke: &Stroke,
+        draw: &DrawState,
+    ) -> Self {
+        let center = point(x as f32, y as f32);
+
+        let mut painter = Painter::new(draw, draw.size().1 as f32);
+        match tool {
+            // a dot of the line that would be drawn here
+            Tool::Pen | Tool::Laser | Tool::Line | Tool::Rectangle | Tool::Ellipse => {
+                let radius = (stroke.width / 2.0).max(MIN_RADIUS);
+                painter.fill(&ring(center, radius + OUTLINE_WIDTH, OUTLINE_WIDTH), &LIGHT);
+                painter.fill(&super::ui::circle(center, radius), &stroke.color);
+            }
+            // everything touching the ring is erased, light and dark so it shows on any line
+            Tool::Eraser => {
+                let radius = super::draw::eraser_radius(stroke.width).max(MIN_RADIUS);
+                painter.fill(&ring(center, radius, OUTLINE_WIDTH), &LIGHT);
+                painter.fill(&ring(center, radius - OUTLINE_WIDTH, OUTLINE_WIDTH), &DARK);
+                painter.fill(&super::ui::circle(center, OUTLINE_WIDTH), &LIGHT);
+            }
+        }
+
+        Self {
+            device,
+            geometry: painter.finish(),
+        }
+    }
+
+    /// A plain dot in place of the brush, for pointing at the toolbar and the radial menu
+    pub fn pointer(device: Device, (x, y): (f64, f64), draw: &DrawState) -> Self {
+        let center = point(x as f32, y as f32);
+
+        let mut painter = Painter::new(draw, draw.size().1 as f32);
+        painter.fill(
+            &super::ui::circle(center, POINTER_RADIUS + OUTLINE_WIDTH),
+            &DARK,
+        );
+        painter.fill(&super::ui::circle(center, POINTER_RADIUS), &LIGHT);
+
+        Self {
+            device,
+            geometry: painter.finish(),
+        }
+    }
+
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+}
+
+/// A circle of `width` inside `radius`
+fn ring(center: Point, radius: f32, width: f32) -> Path {
+    use lyon::path::Winding;
+
+    // the inner circle cuts a hole into the outer one
+    let mut builder = Path::builder();
+    builder.add_circle(center, radius, Winding::Positive);
+    builder.add_circle(center, (radius - width).max(0.0), Winding::Negative);
+    builder.build()
+}
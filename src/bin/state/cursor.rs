@@ -0,0 +1,186 @@
+use wayland_client::Connection;
+use wayland_client::QueueHandle;
+
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_pointer::WlPointer;
+use wayland_client::protocol::wl_shm::WlShm;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::WpCursorShapeDeviceV1;
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_manager_v1::WpCursorShapeManagerV1;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::ZwpTabletToolV2;
+
+use wayland_cursor::CursorTheme;
+
+use clap::ValueEnum;
+
+use log::Level;
+use log::log;
+
+/// Names of the crosshair in cursor themes, in order of preference
+const THEME_CURSORS: [&str; 4] = ["crosshair", "cross", "default", "left_ptr"];
+/// Cursor size when `XCURSOR_SIZE` isn't set
+const THEME_CURSOR_SIZE: u32 = 24;
+
+/// What the pointer and tablet tools look like over an overlay
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum CursorStyle {
+    /// the crosshair of the system
+    Crosshair,
+    /// no system cursor, the overlay draws the outline of what the tool is about to draw or erase
+    Brush,
+}
+
+/// The crosshair of a single pointer or tablet tool
+pub enum Cursor {
+    Shape(WpCursorShapeDeviceV1),
+    Themed {
+        surface: WlSurface,
+        hotspot: (i32, i32),
+    },
+    /// the overlay draws a brush instead
+    Hidden,
+}
+
+impl Cursor {
+    pub fn set_for_pointer(&self, pointer: &WlPointer, serial: u32) {
+        match self {
+            Cursor::Shape(device) => device.set_shape(serial, Shape::Crosshair),
+            Cursor::Themed { surface, hotspot } => {
+                pointer.set_cursor(serial, Some(surface), hotspot.0, hotspot.1)
+            }
+            Cursor::Hidden => pointer.set_cursor(serial, None, 0, 0),
+        }
+    }
+
+    pub fn set_for_tablet_tool(&self, tablet_tool: &ZwpTabletToolV2, serial: u32) {
+        match self {
+            Cursor::Shape(device) => device.set_shape(serial, Shape::Crosshair),
+            Cursor::Themed { surface, hotspot } => {
+                tablet_tool.set_cursor(serial, Some(surface), hotspot.0, hotspot.1)
+            }
+            Cursor::Hidden => tablet_tool.set_cursor(serial, None, 0, 0),
+        }
+    }
+
+    pub fn destroy(self) {
+        match self {
+            Cursor::Shape(device) => device.destroy(),
+            Cursor::Themed { surface, .. } => surface.destroy(),
+            Cursor::Hidden => {}
+        }
+    }
+}
+
+/// Hands out cursors through the cursor shape protocol, or else from the XCursor theme
+pub struct Cursors {
+    style: CursorStyle,
+    shape_manager: Option<WpCursorShapeManagerV1>,
+    theme: Option<CursorTheme>,
+}
+
+impl Cursors {
+    pub fn new(
+        connection: &Connection,
+        style: CursorStyle,
+        shape_manager: Option<WpCursorShapeManagerV1>,
+        shm: Option<WlShm>,
+    ) -> Self {
+        let theme = match (style, &shape_manager, shm) {
+            (CursorStyle::Crosshair, None, Some(shm)) => {
+                // reads XCURSOR_THEME and XCURSOR_SIZE
+                match CursorTheme::load_or(connection, shm, "default", THEME_CURSOR_SIZE) {
+                    Ok(theme) => Some(theme),
+                    Err(e) => {
+                        log!(target: "chameleos::wayland", Level::Warn, "couldn't load cursor theme: {e}");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        Self {
+            style,
+            shape_manager,
+            theme,
+        }
+    }
+
+    /// Whether the overlays draw a brush in place of the system cursor
+    pub fn draws_brush(&self) -> bool {
+        self.style == CursorStyle::Brush
+    }
+
+    pub fn has_shapes(&self) -> bool {
+        self.shape_manager.is_some()
+    }
+
+    pub fn has_theme(&self) -> bool {
+        self.theme.is_some()
+    }
+
+    pub fn for_pointer(
+        &mut self,
+        pointer: &WlPointer,
+        compositor: &WlCompositor,
+        qhandle: &QueueHandle<super::State>,
+    ) -> Option<Cursor> {
+        if self.draws_brush() {
+            return Some(Cursor::Hidden);
+        }
+
+        match self.shape_manager {
+            Some(ref manager) => Some(Cursor::Shape(manager.get_pointer(pointer, qhandle, ()))),
+            None => self.themed(compositor, qhandle),
+        }
+    }
+
+    pub fn for_tablet_tool(
+        &mut self,
+        tablet_tool: &ZwpTabletToolV2,
+        compositor: &WlCompositor,
+        qhandle: &QueueHandle<super::State>,
+    ) -> Option<Cursor> {
+        if self.draws_brush() {
+            return Some(Cursor::Hidden);
+        }
+
+        match self.shape_manager {
+            Some(ref manager) => Some(Cursor::Shape(manager.get_tablet_tool_v2(
+                tablet_tool,
+                qhandle,
+                (),
+            ))),
+            None => self.themed(compositor, qhandle),
+        }
+    }
+
+    /// Every device needs its own surface, a surface can only be the cursor of one of them
+    fn themed(
+        &mut self,
+        compositor: &WlCompositor,
+        qhandle: &QueueHandle<super::State>,
+    ) -> Option<Cursor> {
+        let theme = self.theme.as_mut()?;
+        let name = THEME_CURSORS
+            .into_iter()
+            .find(|&name| theme.get_cursor(name).is_some())?;
+        let cursor = theme.get_cursor(name)?;
+
+        // the crosshair isn't animated, so the first frame is all there is
+        let image = &cursor[0];
+        let (x, y) = image.hotspot();
+
+        let surface = compositor.create_surface(qhandle, ());
+        surface.attach(Some(image), 0, 0);
+        surface.commit();
+
+        Some(Cursor::Themed {
+            surface,
+            hotspot: (x as i32, y as i32),
+        })
+    }
+}
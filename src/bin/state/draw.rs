@@ -1,234 +1,601 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use chameleos::Preset;
+use chameleos::Tool;
+
+use super::input::Device;
+
 use crate::render::Geometry;
 use crate::render::WgpuState;
 
+/// How long the stroke preview stays on screen after the stroke changed
+const PREVIEW_DURATION: Duration = Duration::from_millis(700);
+/// How long a laser line takes to fade away after letting go
+const LASER_FADE: Duration = Duration::from_millis(1000);
+/// Ellipses are drawn as polygons with this many corners
+const ELLIPSE_SEGMENTS: usize = 64;
+
+/// Width and color a line is drawn with
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub color: csscolorparser::Color,
+}
+
+impl Stroke {
+    /// Overrides whatever settings the preset specifies
+    pub fn with_preset(&self, preset: &Preset) -> Self {
+        Self {
+            width: preset.width.unwrap_or(self.width),
+            color: preset.color.clone().unwrap_or_else(|| self.color.clone()),
+        }
+    }
+}
+
+/// How far from the cursor the eraser reaches, it grows with the stroke width
+pub fn eraser_radius(width: f32) -> f32 {
+    (width * 10.0).sqrt()
+}
+
+/// The corners of what a tool draws through its points, and whether they go around back to the
+/// start
+fn outline(tool: Tool, points: &[(f32, f32)]) -> Option<(Vec<(f32, f32)>, bool)> {
+    let (&(x0, y0), &(x1, y1)) = (points.first()?, points.last()?);
+    if points.len() == 1 {
+        return Some((points.to_vec(), false));
+    }
+
+    Some(match tool {
+        Tool::Pen | Tool::Eraser | Tool::Laser => (points.to_vec(), false),
+        Tool::Line => (vec![(x0, y0), (x1, y1)], false),
+        Tool::Rectangle => (vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)], true),
+        Tool::Ellipse => {
+            let (center_x, center_y) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+            let (radius_x, radius_y) = ((x1 - x0).abs() / 2.0, (y1 - y0).abs() / 2.0);
+            let corners = (0..ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                    (
+                        center_x + radius_x * angle.cos(),
+                        center_y + radius_y * angle.sin(),
+                    )
+                })
+                .collect();
+            (corners, true)
+        }
+    })
+}
+
+struct CurrentLine {
+    /// lines in progress are kept per device, so that several can be drawn at once
+    source: Device,
+    tool: Tool,
+    stroke: Stroke,
+    /// for shapes only the start and the end point
+    points: Vec<(f32, f32)>,
+    /// the share of the stroke width at each point, shapes ignore it
+    widths: Vec<f32>,
+}
+
+/// A finished line, the path and stroke are kept around to tessellate it again
+struct Line {
+    stroke: Stroke,
+    path: lyon::path::Path,
+    geometry: Geometry,
+}
+
+/// The lines drawn on one overlay
+#[derive(Default)]
 pub struct DrawState {
     changed: bool,
 
+    width: u32,
     height: u32,
-    stroke_width: f32,
-    stroke_color: csscolorparser::Color,
     color_needs_pre_multiply: bool,
-    current_line: Vec<(f32, f32)>,
-    tessellated_lines: Vec<Geometry>,
-    tessellated_lines_source: Vec<lyon::path::Path>,
+    current_lines: Vec<CurrentLine>,
+    lines: Vec<Line>,
+    /// lines taken back by undo, until a new line is drawn
+    undone_lines: Vec<Line>,
+    /// laser lines that were let go of, fading away since then
+    laser_lines: Vec<(Line, Instant)>,
+    /// where to show the size and color of a stroke, and until when
+    preview: Option<((f64, f64), Stroke, Instant)>,
 }
 
 impl DrawState {
-    pub fn new(stroke_width: f32, stroke_color: csscolorparser::Color) -> Self {
-        Self {
-            changed: false,
-            height: 0,
-            stroke_width,
-            stroke_color,
-            color_needs_pre_multiply: false,
-            current_line: Vec::new(),
-            tessellated_lines: Vec::new(),
-            tessellated_lines_source: Vec::new(),
-        }
-    }
+    /// Adapts the lines to a new overlay size, either keeping them where they are
+    /// or stretching them along
+    pub fn resize(&mut self, width: u32, height: u32, rescale: bool) {
+        use lyon::math::Transform;
 
-    pub fn set_height(&mut self, height: u32) {
+        let (old_width, old_height) = (self.width, self.height);
+        self.width = width;
         self.height = height;
-    }
 
-    pub fn set_stroke_width(&mut self, width: f32) {
-        self.stroke_width = width;
+        if old_width == 0 || old_height == 0 || (old_width, old_height) == (width, height) {
+            return;
+        }
+
+        let sources: Vec<Device> = self.current_lines.iter().map(|l| l.source).collect();
+        for source in sources {
+            self.cut_line(source);
+        }
+        self.laser_lines.clear();
+
+        let (transform, width_scale) = if rescale {
+            let scale_x = width as f32 / old_width as f32;
+            let scale_y = height as f32 / old_height as f32;
+            (Transform::scale(scale_x, scale_y), scale_x.min(scale_y))
+        } else {
+            // y goes upwards from the bottom, so lines have to move to stay at the same spot
+            let offset = height as f32 - old_height as f32;
+            (Transform::translation(0.0, offset), 1.0)
+        };
+
+        for line in self.lines.iter_mut().chain(self.undone_lines.iter_mut()) {
+            line.path = line.path.clone().transformed(&transform);
+            line.stroke.width *= width_scale;
+        }
+        self.retessellate();
     }
 
-    pub fn set_stroke_color(&mut self, color: csscolorparser::Color) {
-        self.stroke_color = color;
-        if self.color_needs_pre_multiply {
-            self.pre_multiply_stroke_color();
+    /// Takes over the lines of another overlay
+    pub fn merge(&mut self, mut other: DrawState, rescale: bool) {
+        if self.width != 0 && self.height != 0 {
+            other.resize(self.width, self.height, rescale);
+        }
+
+        let sources: Vec<Device> = other.current_lines.iter().map(|l| l.source).collect();
+        for source in sources {
+            other.cut_line(source);
         }
+
+        self.lines.append(&mut other.lines);
+        self.retessellate();
     }
 
     pub fn set_pre_multiply_stroke_color(&mut self, b: bool) {
-        if b {
-            self.pre_multiply_stroke_color();
+        if self.color_needs_pre_multiply != b {
+            self.color_needs_pre_multiply = b;
+            self.retessellate();
         }
-        self.color_needs_pre_multiply = b;
     }
 
-    fn pre_multiply_stroke_color(&mut self) {
-        self.stroke_color.r *= self.stroke_color.a;
-        self.stroke_color.g *= self.stroke_color.a;
-        self.stroke_color.b *= self.stroke_color.a;
+    fn retessellate(&mut self) {
+        for i in 0..self.lines.len() {
+            self.lines[i].geometry =
+                self.tessellate_path(&self.lines[i].path, &self.lines[i].stroke);
+        }
+        for i in 0..self.undone_lines.len() {
+            self.undone_lines[i].geometry =
+                self.tessellate_path(&self.undone_lines[i].path, &self.undone_lines[i].stroke);
+        }
+        self.changed = true;
     }
 
-    pub fn render(&mut self, wgpu: &WgpuState) {
+    /// Shows a dot the size and color of a stroke for a moment
+    pub fn show_preview(&mut self, pos: (f64, f64), stroke: &Stroke) {
+        self.preview = Some((pos, stroke.clone(), Instant::now() + PREVIEW_DURATION));
+        self.changed = true;
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Makes the next frame render even if no line changed
+    pub fn set_changed(&mut self) {
+        self.changed = true;
+    }
+
+    /// `ui` is drawn on top of the lines, e.g. the toolbar
+    pub fn render(&mut self, wgpu: &WgpuState, ui: &[&Geometry]) {
+        if let Some((_, _, until)) = self.preview
+            && Instant::now() >= until
+        {
+            self.preview = None;
+            self.changed = true;
+        }
+
+        // fading takes a new frame every time
+        if !self.laser_lines.is_empty() {
+            self.laser_lines
+                .retain(|(_, since)| since.elapsed() < LASER_FADE);
+            self.changed = true;
+        }
+
         if self.changed {
-            self.force_render(wgpu);
+            self.force_render(wgpu, ui);
         }
     }
 
-    pub fn force_render(&mut self, wgpu: &WgpuState) {
-        if let Some((current_line_geometry, _)) = self.tessellate_current_line() {
-            wgpu.render(
-                self.tessellated_lines
-                    .iter()
-                    .chain(std::iter::once(&current_line_geometry)),
-            );
-        } else {
-            wgpu.render(&self.tessellated_lines);
-        }
+    pub fn force_render(&mut self, wgpu: &WgpuState, ui: &[&Geometry]) {
+        let current_line_geometries: Vec<Geometry> = self
+            .current_lines
+            .iter()
+            .filter_map(|line| self.tessellate_line(line))
+            .map(|line| line.geometry)
+            .collect();
+
+        let laser_line_geometries: Vec<Geometry> = self
+            .laser_lines
+            .iter()
+            .map(|(line, since)| {
+                let faded = 1.0 - since.elapsed().as_secs_f32() / LASER_FADE.as_secs_f32();
+                let mut stroke = line.stroke.clone();
+                stroke.color.a *= faded.clamp(0.0, 1.0);
+                self.tessellate_path(&line.path, &stroke)
+            })
+            .collect();
+
+        let preview = self
+            .preview
+            .as_ref()
+            .map(|(pos, stroke, _)| self.tessellate_dot(*pos, stroke));
+
+        wgpu.render(
+            self.lines
+                .iter()
+                .map(|line| &line.geometry)
+                .chain(laser_line_geometries.iter())
+                .chain(current_line_geometries.iter())
+                .chain(preview.iter())
+                .chain(ui.iter().copied()),
+        );
 
         self.changed = false;
     }
 
-    pub fn add_point_to_line(&mut self, (mouse_x, mouse_y): (f64, f64)) {
+    /// `width` is the share of the stroke width to draw with at this point
+    pub fn use_tool(
+        &mut self,
+        source: Device,
+        tool: Tool,
+        stroke: &Stroke,
+        pos: (f64, f64),
+        width: f32,
+    ) {
+        match tool {
+            Tool::Eraser => self.erase(stroke.width, pos),
+            Tool::Pen | Tool::Laser | Tool::Line | Tool::Rectangle | Tool::Ellipse => {
+                self.add_point_to_line(source, tool, stroke, pos, width)
+            }
+        }
+    }
+
+    pub fn add_point_to_line(
+        &mut self,
+        source: Device,
+        tool: Tool,
+        stroke: &Stroke,
+        (mouse_x, mouse_y): (f64, f64),
+        width: f32,
+    ) {
         let new_x = mouse_x as f32;
         let new_y = self.height as f32 - mouse_y as f32;
-        match self.current_line.last() {
+
+        let index = match self.current_lines.iter().position(|l| l.source == source) {
+            Some(i)
+                if self.current_lines[i].stroke == *stroke
+                    && self.current_lines[i].tool == tool =>
+            {
+                i
+            }
+            existing => {
+                if existing.is_some() {
+                    self.cut_line(source);
+                }
+                self.current_lines.push(CurrentLine {
+                    source,
+                    tool,
+                    stroke: stroke.clone(),
+                    points: Vec::new(),
+                    widths: Vec::new(),
+                });
+                self.current_lines.len() - 1
+            }
+        };
+        let line = &mut self.current_lines[index];
+
+        match line.points.last() {
             Some((x, y)) => {
                 if f32::abs(x - new_x) + f32::abs(y - new_y) > crate::EPSILON {
-                    self.current_line.push((new_x, new_y));
+                    // shapes only move their end point along
+                    if tool.is_shape() && line.points.len() > 1 {
+                        line.points.pop();
+                        line.widths.pop();
+                    }
+                    line.points.push((new_x, new_y));
+                    line.widths.push(width);
                     self.changed = true;
                 }
             }
             None => {
-                self.current_line.push((new_x, new_y));
+                line.points.push((new_x, new_y));
+                line.widths.push(width);
                 self.changed = true;
             }
         }
 
         // lines shouldn't get *too* long or it'll cause performance issues
         // also lyon has an upper limit at some point
-        if self.current_line.len() > 0x800 {
-            let (line, path) = self.tessellate_current_line().unwrap();
-            self.tessellated_lines.push(line);
-            self.tessellated_lines_source.push(path);
-            self.current_line.clear();
+        if line.points.len() > 0x800 {
+            let line = self.tessellate_line(&self.current_lines[index]).unwrap();
+            self.push_line(tool, line);
+            self.current_lines[index].points.clear();
+            self.current_lines[index].widths.clear();
+            self.changed = true;
+        }
+    }
+
+    pub fn cut_line(&mut self, source: Device) {
+        if let Some(i) = self.current_lines.iter().position(|l| l.source == source) {
+            let current_line = self.current_lines.remove(i);
+            if let Some(line) = self.tessellate_line(&current_line) {
+                self.push_line(current_line.tool, line);
+            }
+        }
+    }
+
+    /// Keeps a finished line, unless it was drawn with the laser and only fades away
+    fn push_line(&mut self, tool: Tool, line: Line) {
+        if tool == Tool::Laser {
+            self.laser_lines.push((line, Instant::now()));
             self.changed = true;
+            return;
         }
+
+        self.lines.push(line);
+        self.undone_lines.clear();
     }
 
-    pub fn cut_line(&mut self) {
-        if let Some((tesselated_line, path)) = self.tessellate_current_line() {
-            self.tessellated_lines.push(tesselated_line);
-            self.tessellated_lines_source.push(path);
+    /// Drops a line in progress without keeping it
+    pub fn discard_line(&mut self, source: Device) {
+        if let Some(i) = self.current_lines.iter().position(|l| l.source == source) {
+            self.current_lines.remove(i);
+            self.changed = true;
         }
-        self.current_line.clear();
     }
 
     pub fn undo(&mut self) {
-        if self.current_line.is_empty() {
-            self.tessellated_lines.pop();
-            self.tessellated_lines_source.pop();
+        if self.current_lines.iter().all(|line| line.points.is_empty()) {
+            if let Some(line) = self.lines.pop() {
+                self.undone_lines.push(line);
+            }
         } else {
-            self.current_line.clear();
+            self.current_lines.clear();
         }
 
         self.changed = true;
     }
 
+    pub fn redo(&mut self) {
+        if let Some(line) = self.undone_lines.pop() {
+            self.lines.push(line);
+            self.changed = true;
+        }
+    }
+
     pub fn clear(&mut self) {
-        self.tessellated_lines.clear();
-        self.tessellated_lines_source.clear();
-        self.current_line.clear();
+        self.lines.clear();
+        self.current_lines.clear();
+        self.undone_lines.clear();
+        self.laser_lines.clear();
 
         self.changed = true;
     }
 
-    pub fn erase(&mut self, (mouse_x, mouse_y): (f64, f64)) {
+    pub fn erase(&mut self, width: f32, (mouse_x, mouse_y): (f64, f64)) {
         let x = mouse_x as f32;
         let y = self.height as f32 - mouse_y as f32;
 
         let p = lyon::math::point(x, y);
 
-        let eraser_size = self.stroke_width * 10.0;
-
-        let mut to_remove = None;
-
-        for (i, line) in self.tessellated_lines_source.iter().enumerate() {
-            // simple distance check from each point to our cursor
-            // we could also use lyon::math::hit_test
-            // but that has caused problems with short paths
-            for event in line {
-                match event {
-                    lyon::path::Event::Begin { at } => {
-                        if (at - p).square_length() < eraser_size {
-                            to_remove = Some(i);
-                            break;
-                        }
-                    }
-                    lyon::path::Event::Line { from: _, to } => {
-                        if (to - p).square_length() < eraser_size {
-                            to_remove = Some(i);
-                            break;
-                        }
-                    }
-                    lyon::path::Event::End {
-                        last: _,
-                        first: _,
-                        close: _,
-                    } => {}
-                    lyon::path::Event::Quadratic {
-                        from: _,
-                        ctrl: _,
-                        to: _,
-                    } => unreachable!(),
-                    lyon::path::Event::Cubic {
-                        from: _,
-                        ctrl1: _,
-                        ctrl2: _,
-                        to: _,
-                    } => unreachable!(),
+        let eraser_size = eraser_radius(width).powi(2);
+
+        // distance from the cursor to each segment, shapes have long ones
+        // we could also use lyon::math::hit_test
+        // but that has caused problems with short paths
+        let touches = |from, to| {
+            lyon::geom::LineSegment { from, to }.square_distance_to_point(p) < eraser_size
+        };
+        // the line on top goes first
+        let to_remove = self.lines.iter().rposition(|line| {
+            line.path.iter().any(|event| match event {
+                lyon::path::Event::Begin { at } => touches(at, at),
+                lyon::path::Event::Line { from, to } => touches(from, to),
+                lyon::path::Event::End { last, first, close } => close && touches(last, first),
+                lyon::path::Event::Quadratic { .. } | lyon::path::Event::Cubic { .. } => {
+                    unreachable!()
                 }
-            }
-        }
+            })
+        });
 
         if let Some(i) = to_remove {
-            self.tessellated_lines.remove(i);
-            self.tessellated_lines_source.remove(i);
+            self.lines.remove(i);
 
             self.changed = true;
         }
     }
 
-    fn tessellate_current_line(&self) -> Option<(Geometry, lyon::path::Path)> {
-        use crate::render::Vertex;
+    fn tessellate_line(&self, line: &CurrentLine) -> Option<Line> {
         use lyon::math::point;
         use lyon::path::Path;
+
+        let stroke = &line.stroke;
+        let (corners, close) = outline(line.tool, &line.points)?;
+        // the corners of everything but shapes are the points themselves
+        let width = |i: usize| {
+            if line.tool.is_shape() {
+                1.0
+            } else {
+                line.widths[i]
+            }
+        };
+
+        let mut builder = Path::builder_with_attributes(1);
+        builder.begin(point(corners[0].0, corners[0].1), &[width(0)]);
+        // small hack for drawing dots
+        builder.line_to(point(corners[0].0, corners[0].1), &[width(0)]);
+        for (i, &(x, y)) in corners.iter().enumerate().skip(1) {
+            builder.line_to(point(x, y), &[width(i)]);
+        }
+        builder.end(close);
+        let path = builder.build();
+
+        Some(Line {
+            stroke: stroke.clone(),
+            geometry: self.tessellate_path(&path, stroke),
+            path,
+        })
+    }
+
+    fn tessellate_path(&self, path: &lyon::path::Path, stroke: &Stroke) -> Geometry {
+        use crate::render::Vertex;
         use lyon::tessellation::BuffersBuilder;
         use lyon::tessellation::StrokeOptions;
         use lyon::tessellation::StrokeTessellator;
         use lyon::tessellation::StrokeVertex;
         use lyon::tessellation::VertexBuffers;
 
-        let line = &self.current_line;
-
         let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
-
-        let mut builder = Path::builder();
-        if line.len() < 1 {
-            return None;
-        }
-
-        builder.begin(point(line[0].0, line[0].1));
-        // small hack for drawing dots
-        builder.line_to(point(line[0].0, line[0].1));
-        for &(x, y) in line.iter().skip(1) {
-            builder.line_to(point(x, y));
-        }
-        builder.end(false);
-        let path = builder.build();
+        let color = self.vertex_color(&stroke.color);
 
         let mut tessellator = StrokeTessellator::new();
+        // scaled by the width of each point of the path
         let stroke_options = StrokeOptions::default()
-            .with_line_width(self.stroke_width)
+            .with_line_width(stroke.width)
+            .with_variable_line_width(0)
             .with_line_cap(lyon::path::LineCap::Round)
             .with_line_join(lyon::path::LineJoin::Round);
 
         tessellator
             .tessellate_path(
-                &path,
+                path,
                 &stroke_options,
                 &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| {
-                    Vertex::new(vertex, &self.stroke_color)
+                    Vertex::new(vertex.position(), &color)
                 }),
             )
             .unwrap();
 
-        Some((Geometry::new(geometry), path))
+        Geometry::new(geometry)
+    }
+
+    fn tessellate_dot(&self, (x, y): (f64, f64), stroke: &Stroke) -> Geometry {
+        use crate::render::Vertex;
+        use lyon::math::point;
+        use lyon::tessellation::BuffersBuilder;
+        use lyon::tessellation::FillOptions;
+        use lyon::tessellation::FillTessellator;
+        use lyon::tessellation::FillVertex;
+        use lyon::tessellation::VertexBuffers;
+
+        let mut geometry: VertexBuffers<Vertex, u16> = VertexBuffers::new();
+        let center = point(x as f32, self.height as f32 - y as f32);
+        let color = self.vertex_color(&stroke.color);
+
+        FillTessellator::new()
+            .tessellate_circle(
+                center,
+                stroke.width / 2.0,
+                &FillOptions::default(),
+                &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| {
+                    Vertex::new(vertex.position(), &color)
+                }),
+            )
+            .unwrap();
+
+        Geometry::new(geometry)
+    }
+
+    pub fn vertex_color(&self, color: &csscolorparser::Color) -> csscolorparser::Color {
+        let mut color = color.clone();
+        if self.color_needs_pre_multiply {
+            color.r *= color.a;
+            color.g *= color.a;
+            color.b *= color.a;
+        }
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_span_their_start_and_end() {
+        let points = [(0.0, 0.0), (5.0, 8.0), (10.0, 20.0)];
+
+        assert_eq!(outline(Tool::Pen, &points), Some((points.to_vec(), false)));
+        assert_eq!(
+            outline(Tool::Line, &points),
+            Some((vec![(0.0, 0.0), (10.0, 20.0)], false))
+        );
+        assert_eq!(
+            outline(Tool::Rectangle, &points),
+            Some((
+                vec![(0.0, 0.0), (10.0, 0.0), (10.0, 20.0), (0.0, 20.0)],
+                true
+            ))
+        );
+
+        let (corners, closed) = outline(Tool::Ellipse, &points).unwrap();
+        assert!(closed);
+        assert_eq!(corners.len(), ELLIPSE_SEGMENTS);
+        for (x, y) in corners {
+            let (dx, dy) = ((x - 5.0) / 5.0, (y - 10.0) / 10.0);
+            assert!((dx * dx + dy * dy - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn shapes_that_were_only_clicked_are_dots() {
+        for tool in [Tool::Line, Tool::Rectangle, Tool::Ellipse] {
+            assert_eq!(
+                outline(tool, &[(3.0, 4.0)]),
+                Some((vec![(3.0, 4.0)], false))
+            );
+        }
+        assert_eq!(outline(Tool::Pen, &[]), None);
+    }
+
+    /// The share of the stroke width at each point of a line drawn through the samples
+    fn widths(tool: Tool, samples: &[((f64, f64), f32)]) -> Vec<f32> {
+        let mut draw = DrawState::default();
+        draw.resize(100, 100, false);
+        let stroke = Stroke {
+            width: 10.0,
+            color: csscolorparser::parse("red").unwrap(),
+        };
+        for &(pos, width) in samples {
+            draw.use_tool(Device::Mouse, tool, &stroke, pos, width);
+        }
+
+        let line = draw.tessellate_line(&draw.current_lines[0]).unwrap();
+        line.path
+            .iter_with_attributes()
+            .filter_map(|event| match event {
+                lyon::path::Event::Begin { at: (_, width) } => Some(width[0]),
+                lyon::path::Event::Line { to: (_, width), .. } => Some(width[0]),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lines_get_the_width_of_each_point() {
+        let samples = [
+            ((10.0, 10.0), 1.0),
+            ((20.0, 10.0), 0.5),
+            ((30.0, 20.0), 0.2),
+        ];
+
+        // the first point is there twice to draw dots
+        assert_eq!(widths(Tool::Pen, &samples), [1.0, 1.0, 0.5, 0.2]);
+        assert_eq!(widths(Tool::Pen, &samples[1..2]), [0.5, 0.5]);
+        assert_eq!(widths(Tool::Rectangle, &samples), [1.0; 5]);
     }
 }
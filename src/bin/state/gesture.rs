@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::Proxy;
+use wayland_client::QueueHandle;
+
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1;
+
+use wayland_client::protocol::wl_pointer::WlPointer;
+
+use log::Level;
+use log::log;
+
+use chameleos::Command;
+use chameleos::Gesture;
+use chameleos::GestureBinding;
+use chameleos::GestureKind;
+
+/// Fingers need to move this far (in surface coordinates) for a swipe
+const SWIPE_DISTANCE: f64 = 100.0;
+/// Fingers need to move this much closer together or further apart for a pinch
+const PINCH_SCALE: f64 = 0.25;
+
+struct Swipe {
+    fingers: u32,
+    dx: f64,
+    dy: f64,
+}
+
+struct Pinch {
+    fingers: u32,
+    scale: f64,
+}
+
+/// The gesture objects of the seat's pointer
+struct Recognizers {
+    swipe: ZwpPointerGestureSwipeV1,
+    pinch: ZwpPointerGesturePinchV1,
+    hold: Option<ZwpPointerGestureHoldV1>,
+}
+
+pub struct GestureState {
+    commands: HashMap<Gesture, Command>,
+    recognizers: Option<Recognizers>,
+
+    swipe: Option<Swipe>,
+    pinch: Option<Pinch>,
+    hold: Option<u32>,
+}
+
+impl GestureState {
+    pub fn new(bindings: &[GestureBinding]) -> Self {
+        let mut commands = HashMap::from([
+            (
+                Gesture {
+                    kind: GestureKind::SwipeLeft,
+                    fingers: Some(3),
+                },
+                Command::Undo,
+            ),
+            (
+                Gesture {
+                    kind: GestureKind::SwipeRight,
+                    fingers: Some(3),
+                },
+                Command::Redo,
+            ),
+        ]);
+
+        for binding in bindings {
+            commands.insert(binding.gesture, binding.command.clone());
+        }
+
+        Self {
+            commands,
+            recognizers: None,
+            swipe: None,
+            pinch: None,
+            hold: None,
+        }
+    }
+
+    /// Starts recognizing gestures on a new pointer
+    pub fn attach(
+        &mut self,
+        pointer: &WlPointer,
+        pointer_gestures: &ZwpPointerGesturesV1,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        self.detach();
+        self.recognizers = Some(Recognizers {
+            swipe: pointer_gestures.get_swipe_gesture(pointer, qhandle, ()),
+            pinch: pointer_gestures.get_pinch_gesture(pointer, qhandle, ()),
+            // hold gestures were added in version 3
+            hold: (pointer_gestures.version() >= 3)
+                .then(|| pointer_gestures.get_hold_gesture(pointer, qhandle, ())),
+        });
+    }
+
+    /// Stops recognizing gestures on a pointer that went away
+    pub fn detach(&mut self) {
+        if let Some(recognizers) = self.recognizers.take() {
+            recognizers.swipe.destroy();
+            recognizers.pinch.destroy();
+            if let Some(hold) = recognizers.hold {
+                hold.destroy();
+            }
+        }
+        self.swipe = None;
+        self.pinch = None;
+        self.hold = None;
+    }
+
+    /// A command bound to this exact finger count wins over one bound to any finger count
+    fn command(&self, kind: GestureKind, fingers: u32) -> Option<Command> {
+        self.commands
+            .get(&Gesture {
+                kind,
+                fingers: Some(fingers),
+            })
+            .or_else(|| {
+                self.commands.get(&Gesture {
+                    kind,
+                    fingers: None,
+                })
+            })
+            .cloned()
+    }
+}
+
+fn run_gesture(
+    state: &mut super::State,
+    kind: GestureKind,
+    fingers: u32,
+    qhandle: &QueueHandle<super::State>,
+) {
+    log!(target: "chameleos::general", Level::Info, "{kind:?} gesture with {fingers} fingers");
+
+    if state.overlays.any_active()
+        && let Some(command) = state.gesture.command(kind, fingers)
+    {
+        state.execute(command, qhandle);
+    }
+}
+
+impl Dispatch<ZwpPointerGestureSwipeV1, (), super::State> for GestureState {
+    fn event(
+        state: &mut super::State,
+        _swipe: &ZwpPointerGestureSwipeV1,
+        event: <ZwpPointerGestureSwipeV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "ZwpPointerGestureSwipeV1: {:?}", event);
+
+        use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_swipe_v1::Event;
+        match event {
+            Event::Begin {
+                serial: _,
+                time: _,
+                surface: _,
+                fingers,
+            } => {
+                state.gesture.swipe = Some(Swipe {
+                    fingers,
+                    dx: 0.0,
+                    dy: 0.0,
+                });
+            }
+            Event::Update { time: _, dx, dy } => {
+                if let Some(ref mut swipe) = state.gesture.swipe {
+                    swipe.dx += dx;
+                    swipe.dy += dy;
+                }
+            }
+            Event::End {
+                serial: _,
+                time: _,
+                cancelled,
+            } => {
+                if let Some(swipe) = state.gesture.swipe.take()
+                    && cancelled == 0
+                {
+                    let kind = if swipe.dx.abs() > swipe.dy.abs() {
+                        if swipe.dx < 0.0 {
+                            GestureKind::SwipeLeft
+                        } else {
+                            GestureKind::SwipeRight
+                        }
+                    } else if swipe.dy < 0.0 {
+                        GestureKind::SwipeUp
+                    } else {
+                        GestureKind::SwipeDown
+                    };
+
+                    if swipe.dx.hypot(swipe.dy) >= SWIPE_DISTANCE {
+                        run_gesture(state, kind, swipe.fingers, qhandle);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPointerGesturePinchV1, (), super::State> for GestureState {
+    fn event(
+        state: &mut super::State,
+        _pinch: &ZwpPointerGesturePinchV1,
+        event: <ZwpPointerGesturePinchV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "ZwpPointerGesturePinchV1: {:?}", event);
+
+        use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_pinch_v1::Event;
+        match event {
+            Event::Begin {
+                serial: _,
+                time: _,
+                surface: _,
+                fingers,
+            } => {
+                state.gesture.pinch = Some(Pinch {
+                    fingers,
+                    scale: 1.0,
+                });
+            }
+            Event::Update {
+                time: _,
+                dx: _,
+                dy: _,
+                scale,
+                rotation: _,
+            } => {
+                // the scale is relative to where the fingers started, not to the last update
+                if let Some(ref mut pinch) = state.gesture.pinch {
+                    pinch.scale = scale;
+                }
+            }
+            Event::End {
+                serial: _,
+                time: _,
+                cancelled,
+            } => {
+                if let Some(pinch) = state.gesture.pinch.take()
+                    && cancelled == 0
+                {
+                    if pinch.scale <= 1.0 - PINCH_SCALE {
+                        run_gesture(state, GestureKind::PinchIn, pinch.fingers, qhandle);
+                    } else if pinch.scale >= 1.0 + PINCH_SCALE {
+                        run_gesture(state, GestureKind::PinchOut, pinch.fingers, qhandle);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPointerGestureHoldV1, (), super::State> for GestureState {
+    fn event(
+        state: &mut super::State,
+        _hold: &ZwpPointerGestureHoldV1,
+        event: <ZwpPointerGestureHoldV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "ZwpPointerGestureHoldV1: {:?}", event);
+
+        use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_hold_v1::Event;
+        match event {
+            Event::Begin {
+                serial: _,
+                time: _,
+                surface: _,
+                fingers,
+            } => state.gesture.hold = Some(fingers),
+            Event::End {
+                serial: _,
+                time: _,
+                cancelled,
+            } => {
+                // holds get cancelled as soon as the fingers start moving
+                if let Some(fingers) = state.gesture.hold.take()
+                    && cancelled == 0
+                {
+                    run_gesture(state, GestureKind::Hold, fingers, qhandle);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use lyon::math::Box2D;
+use lyon::math::point;
+use lyon::path::Path;
+
+use clap::ValueEnum;
+
+use super::draw::DrawState;
+use super::ui::Look;
+use super::ui::Painter;
+
+use crate::render::Geometry;
+
+/// Width of the border, in logical pixels
+const BORDER_WIDTH: f32 = 4.0;
+/// Size of the badge
+const BADGE_SIZE: f32 = 44.0;
+/// Space between the badge and the corner of the screen
+const BADGE_MARGIN: f32 = 12.0;
+/// Opacity of the tint
+const TINT_ALPHA: f32 = 0.06;
+
+/// How an overlay shows that it takes input, all of them in the current stroke color
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum IndicatorKind {
+    /// a frame around the screen
+    Border,
+    /// a dot of the current width in the top right corner
+    Badge,
+    /// a faint color over the whole screen
+    Tint,
+    None,
+}
+
+/// Drawn below the toolbar while input is active
+pub struct Indicator {
+    kind: IndicatorKind,
+    look: Look,
+    geometry: Geometry,
+}
+
+impl Indicator {
+    /// `None` for [`IndicatorKind::None`]
+    pub fn new(
+        kind: IndicatorKind,
+        look: Look,
+        (width, height): (u32, u32),
+        draw: &DrawState,
+    ) -> Option<Self> {
+        let (width, height) = (width as f32, height as f32);
+        let screen = Box2D::new(point(0.0, 0.0), point(width, height));
+        let color = &look.stroke.color;
+
+        let mut painter = Painter::new(draw, height);
+        match kind {
+            IndicatorKind::Border => {
+                use lyon::path::Winding;
+
+                // the inner rectangle cuts a hole into the outer one
+                let mut builder = Path::builder();
+                builder.add_rectangle(&screen, Winding::Positive);
+                builder.add_rectangle(
+                    &screen.inflate(-BORDER_WIDTH, -BORDER_WIDTH),
+                    Winding::Negative,
+                );
+                painter.fill(&builder.build(), color);
+            }
+            IndicatorKind::Badge => {
+                let corner = point(width - BADGE_MARGIN - BADGE_SIZE, BADGE_MARGIN);
+                let badge =
+                    Box2D::from_origin_and_size(corner, lyon::math::size(BADGE_SIZE, BADGE_SIZE));
+                let radius = (look.stroke.width / 2.0).clamp(2.0, BADGE_SIZE / 2.0 - 6.0);
+
+                painter.fill(
+                    &super::ui::rounded_rectangle(&badge, BADGE_SIZE / 4.0),
+                    &super::ui::BACKGROUND,
+                );
+                painter.fill(
+                    &super::ui::circle(badge.center(), radius + 1.5),
+                    &csscolorparser::Color::new(1.0, 1.0, 1.0, 1.0),
+                );
+                painter.fill(&super::ui::circle(badge.center(), radius), color);
+            }
+            IndicatorKind::Tint => {
+                let mut tint = color.clone();
+                tint.a *= TINT_ALPHA;
+                let mut builder = Path::builder();
+                builder.add_rectangle(&screen, lyon::path::Winding::Positive);
+                painter.fill(&builder.build(), &tint);
+            }
+            IndicatorKind::None => return None,
+        }
+
+        Some(Self {
+            kind,
+            look,
+            geometry: painter.finish(),
+        })
+    }
+
+    pub fn is_built_for(&self, kind: IndicatorKind, look: &Look) -> bool {
+        self.kind == kind && self.look == *look
+    }
+
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+}
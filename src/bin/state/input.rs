@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use wayland_backend::client::ObjectId;
+
+use log::Level;
+use log::log;
+
+use chameleos::Command;
+use chameleos::Tool;
+
+use super::draw::DrawState;
+use super::draw::Stroke;
+use super::overlay::Overlays;
+
+/// A single pointer-like input, e.g. the mouse, a tablet tool or one finger on a touchscreen
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Device {
+    Mouse,
+    Tablet,
+    Touch(i32),
+}
+
+/// Lines drawn with the lightest touch still get this much of the stroke width
+const MIN_PRESSURE_WIDTH: f32 = 0.2;
+
+/// Where a device is and, if it can tell, how hard it presses and how it is tilted
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    pub pos: (f64, f64),
+    /// normalized to 0..=1, lines get thinner the lighter they are drawn
+    pub pressure: Option<f64>,
+    /// in degrees, not used for drawing so far
+    pub tilt: Option<(f64, f64)>,
+}
+
+impl Sample {
+    pub fn at(pos: (f64, f64)) -> Self {
+        Self {
+            pos,
+            pressure: None,
+            tilt: None,
+        }
+    }
+
+    /// How much of the stroke width a line gets at this sample, devices without pressure draw
+    /// with all of it
+    pub fn width(&self) -> f32 {
+        self.pressure.map_or(1.0, |pressure| {
+            MIN_PRESSURE_WIDTH + (1.0 - MIN_PRESSURE_WIDTH) * pressure.clamp(0.0, 1.0) as f32
+        })
+    }
+}
+
+/// What every device boils down to, no matter if it's a mouse, a pen or a finger
+pub enum ToolEvent {
+    Down {
+        /// the surface of the overlay to draw on, moves stay there until the tool goes up
+        surface: ObjectId,
+        tool: Tool,
+        stroke: Stroke,
+        sample: Sample,
+    },
+    Move {
+        sample: Sample,
+    },
+    Up,
+    /// like `Up`, but the line drawn so far gets thrown away
+    Cancel,
+}
+
+struct ActiveTool {
+    surface: ObjectId,
+    tool: Tool,
+    stroke: Stroke,
+}
+
+/// Applies the tool events of all devices to the drawings of the overlays
+#[derive(Default)]
+pub struct ToolDispatcher {
+    active: HashMap<Device, ActiveTool>,
+}
+
+impl ToolDispatcher {
+    /// Returns the commands of the toolbar button a tool went down on, instead of drawing there
+    pub fn dispatch(
+        &mut self,
+        overlays: &mut Overlays,
+        device: Device,
+        event: ToolEvent,
+    ) -> Vec<Command> {
+        match event {
+            ToolEvent::Down {
+                surface,
+                tool,
+                stroke,
+                sample,
+            } => {
+                if let Some(active) = self.active.remove(&device)
+                    && let Some(overlay) = overlays.get_mut(&active.surface)
+                {
+                    overlay.finish_line(device);
+                }
+
+                if let Some(overlay) = overlays.get_mut(&surface) {
+                    overlay.note_input();
+                }
+
+                if let Some(commands) = overlays.toolbar_hit(&surface, sample.pos) {
+                    return commands;
+                }
+
+                if let Some(draw) = overlays.draw_mut(&surface) {
+                    apply(draw, device, tool, &stroke, sample);
+                    overlays.set_last_drawn(surface.clone());
+                    self.active.insert(
+                        device,
+                        ActiveTool {
+                            surface,
+                            tool,
+                            stroke,
+                        },
+                    );
+                }
+            }
+            ToolEvent::Move { sample } => {
+                if let Some(active) = self.active.get(&device)
+                    && let Some(overlay) = overlays.get_mut(&active.surface)
+                {
+                    overlay.note_input();
+                    apply(
+                        &mut overlay.draw,
+                        device,
+                        active.tool,
+                        &active.stroke,
+                        sample,
+                    );
+                }
+            }
+            ToolEvent::Up => {
+                if let Some(active) = self.active.remove(&device)
+                    && let Some(overlay) = overlays.get_mut(&active.surface)
+                {
+                    overlay.finish_line(device);
+                }
+            }
+            ToolEvent::Cancel => {
+                if let Some(active) = self.active.remove(&device)
+                    && let Some(draw) = overlays.draw_mut(&active.surface)
+                {
+                    draw.discard_line(device);
+                }
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Ends the lines of all devices drawing on an overlay, e.g. when it stops taking input and
+    /// their `Up` would never arrive
+    ///
+    /// Returns the devices that were drawing there.
+    pub fn release(&mut self, overlays: &mut Overlays, surface: &ObjectId) -> Vec<Device> {
+        let devices: Vec<Device> = self
+            .active
+            .iter()
+            .filter(|(_, active)| active.surface == *surface)
+            .map(|(&device, _)| device)
+            .collect();
+
+        for &device in &devices {
+            self.dispatch(overlays, device, ToolEvent::Up);
+        }
+        devices
+    }
+}
+
+fn apply(draw: &mut DrawState, device: Device, tool: Tool, stroke: &Stroke, sample: Sample) {
+    log!(
+        target: "chameleos::general",
+        Level::Trace,
+        "{device:?} {} at {:?}, pressure {:?}, tilt {:?}",
+        tool.name(),
+        sample.pos,
+        sample.pressure,
+        sample.tilt,
+    );
+
+    draw.use_tool(device, tool, stroke, sample.pos, sample.width());
+}
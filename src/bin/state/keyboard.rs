@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::Proxy;
+use wayland_client::QueueHandle;
+use wayland_client::WEnum;
+
+use wayland_client::protocol::wl_keyboard::WlKeyboard;
+use wayland_client::protocol::wl_seat::WlSeat;
+
+use xkbcommon::xkb;
+
+use clap::ValueEnum;
+
+use log::Level;
+use log::log;
+
+use chameleos::Command;
+
+/// Whether active overlays take keyboard focus, which the shortcuts need
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum KeyboardFocus {
+    /// never, all shortcuts have to come from the compositor
+    None,
+    /// when the compositor decides to give it, usually after clicking an overlay
+    OnDemand,
+    /// right away, no other window gets keys until input is deactivated
+    Exclusive,
+}
+
+/// A key with modifiers, written as e.g. `ctrl+shift+z`
+///
+/// Keys are xkb keysym names (e.g. `z`, `1`, `Escape` or `F5`). They match both the unshifted
+/// symbol of a key with Shift given as a modifier, e.g. `ctrl+shift+z`, and the symbol a key
+/// types, e.g. `1` on layouts that need Shift for digits.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Shortcut {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    logo: bool,
+    keysym: xkb::Keysym,
+}
+
+impl std::str::FromStr for Shortcut {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.trim().split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+
+        let keysym = xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE);
+        if keysym == xkb::Keysym::NoSymbol {
+            return Err(format!("unknown key \"{key}\""));
+        }
+
+        let mut shortcut = Self {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            logo: false,
+            keysym,
+        };
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => shortcut.ctrl = true,
+                "shift" => shortcut.shift = true,
+                "alt" => shortcut.alt = true,
+                "super" | "logo" => shortcut.logo = true,
+                _ => return Err(format!("unknown modifier \"{modifier}\"")),
+            }
+        }
+        Ok(shortcut)
+    }
+}
+
+/// A `SHORTCUT=COMMAND` pair as given on the command line
+///
+/// Commands are written the way they are sent over the socket, e.g. `undo` or `stroke_width 16`.
+#[derive(Clone)]
+pub struct ShortcutBinding {
+    pub shortcut: Shortcut,
+    pub command: Command,
+}
+
+impl std::str::FromStr for ShortcutBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // a `=` key is written as `equal`, so the first `=` always separates the command
+        let (shortcut, command) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected SHORTCUT=COMMAND, got \"{s}\""))?;
+        Ok(Self {
+            shortcut: shortcut.parse()?,
+            command: Command::deserialize(command.trim().as_bytes())
+                .map_err(|e| format!("{e}: \"{command}\""))?,
+        })
+    }
+}
+
+pub struct KeyboardState {
+    commands: HashMap<Shortcut, Command>,
+
+    /// the seat's keyboard, if it has one
+    keyboard: Option<WlKeyboard>,
+
+    context: xkb::Context,
+    keymap: Option<xkb::Keymap>,
+    xkb_state: Option<xkb::State>,
+}
+
+impl KeyboardState {
+    pub fn new(bindings: &[ShortcutBinding]) -> Self {
+        let shortcut = |s: &str| s.parse::<Shortcut>().expect("default shortcuts are valid");
+
+        let mut commands = HashMap::from([
+            (shortcut("ctrl+z"), Command::Undo),
+            (shortcut("ctrl+shift+z"), Command::Redo),
+            // only reaches us while input is active
+            (shortcut("Escape"), Command::Deactivate),
+        ]);
+        for index in 1..=9 {
+            commands.insert(shortcut(&index.to_string()), Command::Color { index });
+        }
+
+        for binding in bindings {
+            commands.insert(binding.shortcut, binding.command.clone());
+        }
+
+        Self {
+            commands,
+            keyboard: None,
+            context: xkb::Context::new(xkb::CONTEXT_NO_FLAGS),
+            keymap: None,
+            xkb_state: None,
+        }
+    }
+
+    /// Gets the seat's keyboard when it shows up and lets go of it once it's gone, seats announce
+    /// their capabilities again whenever any device comes or goes
+    pub fn update_capability(
+        &mut self,
+        seat: &WlSeat,
+        has_keyboard: bool,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        match (has_keyboard, self.keyboard.take()) {
+            (true, None) => self.keyboard = Some(seat.get_keyboard(qhandle, ())),
+            (false, Some(keyboard)) => {
+                // releasing was added in version 3
+                if keyboard.version() >= 3 {
+                    keyboard.release();
+                }
+            }
+            (_, keyboard) => self.keyboard = keyboard,
+        }
+    }
+
+    fn command(&self, key: u32) -> Option<Command> {
+        let (keymap, xkb_state) = self.keymap.as_ref().zip(self.xkb_state.as_ref())?;
+
+        // evdev codes are off by 8 from xkb keycodes
+        let keycode = xkb::Keycode::new(key + 8);
+        shortcuts(keymap, xkb_state, keycode)
+            .iter()
+            .find_map(|shortcut| self.commands.get(shortcut))
+            .cloned()
+    }
+}
+
+/// The shortcuts a key press could be meant as, first the unshifted symbol with every modifier
+/// held, then the symbol the key types with the modifiers that went into typing it left out
+fn shortcuts(keymap: &xkb::Keymap, xkb_state: &xkb::State, keycode: xkb::Keycode) -> Vec<Shortcut> {
+    let active = |name| xkb_state.mod_name_is_active(name, xkb::STATE_MODS_EFFECTIVE);
+    let unconsumed = |name| {
+        active(name) && !xkb_state.mod_index_is_consumed(keycode, keymap.mod_get_index(name))
+    };
+
+    let mut shortcuts = Vec::new();
+    let layout = xkb_state.key_get_layout(keycode);
+    if let Some(&keysym) = keymap.key_get_syms_by_level(keycode, layout, 0).first() {
+        shortcuts.push(Shortcut {
+            ctrl: active(xkb::MOD_NAME_CTRL),
+            shift: active(xkb::MOD_NAME_SHIFT),
+            alt: active(xkb::MOD_NAME_ALT),
+            logo: active(xkb::MOD_NAME_LOGO),
+            keysym,
+        });
+    }
+    let keysym = xkb_state.key_get_one_sym(keycode);
+    if keysym != xkb::Keysym::NoSymbol {
+        shortcuts.push(Shortcut {
+            ctrl: unconsumed(xkb::MOD_NAME_CTRL),
+            shift: unconsumed(xkb::MOD_NAME_SHIFT),
+            alt: unconsumed(xkb::MOD_NAME_ALT),
+            logo: unconsumed(xkb::MOD_NAME_LOGO),
+            keysym,
+        });
+    }
+    shortcuts
+}
+
+impl Dispatch<WlKeyboard, (), super::State> for KeyboardState {
+    fn event(
+        state: &mut super::State,
+        _keyboard: &WlKeyboard,
+        event: <WlKeyboard as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Trace, "WlKeyboard: {:?}", event);
+
+        use wayland_client::protocol::wl_keyboard::Event;
+        use wayland_client::protocol::wl_keyboard::KeyState;
+        use wayland_client::protocol::wl_keyboard::KeymapFormat;
+        match event {
+            Event::Keymap {
+                format: WEnum::Value(KeymapFormat::XkbV1),
+                fd,
+                size,
+            } => {
+                let keyboard = &mut state.keyboard;
+                // SAFETY: the compositor hands us a keymap of this size to map
+                let keymap = unsafe {
+                    xkb::Keymap::new_from_fd(
+                        &keyboard.context,
+                        fd,
+                        size as usize,
+                        xkb::KEYMAP_FORMAT_TEXT_V1,
+                        xkb::KEYMAP_COMPILE_NO_FLAGS,
+                    )
+                };
+
+                match keymap {
+                    Ok(Some(keymap)) => {
+                        keyboard.xkb_state = Some(xkb::State::new(&keymap));
+                        keyboard.keymap = Some(keymap);
+                    }
+                    Ok(None) => {
+                        log!(target: "chameleos::wayland", Level::Warn, "couldn't compile keymap")
+                    }
+                    Err(e) => {
+                        log!(target: "chameleos::wayland", Level::Warn, "couldn't read keymap: {e}")
+                    }
+                }
+            }
+            Event::Modifiers {
+                serial: _,
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+            } => {
+                if let Some(ref mut xkb_state) = state.keyboard.xkb_state {
+                    xkb_state.update_mask(mods_depressed, mods_latched, mods_locked, 0, 0, group);
+                }
+            }
+            Event::Key {
+                serial: _,
+                time: _,
+                key,
+                state: WEnum::Value(KeyState::Pressed),
+            } => {
+                if state.overlays.any_active()
+                    && let Some(command) = state.keyboard.command(key)
+                {
+                    state.execute(command, qhandle);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortcut(keysym: &str) -> Shortcut {
+        Shortcut {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            logo: false,
+            keysym: xkb::keysym_from_name(keysym, xkb::KEYSYM_NO_FLAGS),
+        }
+    }
+
+    #[test]
+    fn parses_shortcuts() {
+        assert_eq!("z".parse(), Ok(shortcut("z")));
+        assert_eq!("Z".parse(), Ok(shortcut("z")));
+        assert_eq!("escape".parse(), Ok(shortcut("Escape")));
+        assert_eq!(
+            " Ctrl + shift+z".parse(),
+            Ok(Shortcut {
+                ctrl: true,
+                shift: true,
+                ..shortcut("z")
+            })
+        );
+        assert_eq!(
+            "super+alt+F5".parse(),
+            Ok(Shortcut {
+                alt: true,
+                logo: true,
+                ..shortcut("F5")
+            })
+        );
+        assert_eq!("1".parse(), Ok(shortcut("1")));
+        assert_eq!("equal".parse(), Ok(shortcut("equal")));
+
+        assert!("".parse::<Shortcut>().is_err());
+        assert!("ctrl+".parse::<Shortcut>().is_err());
+        assert!("hyper+z".parse::<Shortcut>().is_err());
+        assert!("ctrl+nokey".parse::<Shortcut>().is_err());
+    }
+
+    #[test]
+    fn parses_shortcut_bindings() {
+        let binding: ShortcutBinding = "ctrl+equal=stroke_width 16".parse().unwrap();
+        assert_eq!(
+            binding.shortcut,
+            Shortcut {
+                ctrl: true,
+                ..shortcut("equal")
+            }
+        );
+        assert!(matches!(
+            binding.command,
+            Command::StrokeWidth { width: 16.0 }
+        ));
+
+        assert!("ctrl+z".parse::<ShortcutBinding>().is_err());
+        assert!("ctrl+z=nonsense".parse::<ShortcutBinding>().is_err());
+    }
+
+    /// Needs the xkeyboard-config layouts, which come along with libxkbcommon
+    fn press(layout: &str, key: u32, shift: bool) -> Vec<Shortcut> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let keymap = xkb::Keymap::new_from_names(
+            &context,
+            "evdev",
+            "pc105",
+            layout,
+            "",
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .expect("layout is installed");
+        let mut xkb_state = xkb::State::new(&keymap);
+        if shift {
+            let mask = 1 << keymap.mod_get_index(xkb::MOD_NAME_SHIFT);
+            xkb_state.update_mask(mask, 0, 0, 0, 0, 0);
+        }
+        shortcuts(&keymap, &xkb_state, xkb::Keycode::new(key + 8))
+    }
+
+    #[test]
+    fn digits_match_on_layouts_that_shift_them() {
+        const KEY_1: u32 = 2;
+        const KEY_Z: u32 = 44;
+
+        assert!(press("us", KEY_1, false).contains(&shortcut("1")));
+        // on AZERTY the key left of `2` types `&` and needs Shift for `1`
+        assert!(!press("fr", KEY_1, false).contains(&shortcut("1")));
+        assert!(press("fr", KEY_1, true).contains(&shortcut("1")));
+
+        let shift_z = "shift+z".parse().unwrap();
+        assert!(press("us", KEY_Z, true).contains(&shift_z));
+    }
+}
@@ -1,6 +1,32 @@
+mod bindings;
+mod brush;
+mod cursor;
 mod draw;
+mod gesture;
+mod indicator;
+mod input;
+mod keyboard;
 mod mouse;
+mod output;
+mod overlay;
+mod pad;
+mod palette;
+mod radial;
+mod shell;
 mod tablet;
+mod toolbar;
+mod touch;
+mod ui;
+
+pub use cursor::CursorStyle;
+pub use indicator::IndicatorKind;
+pub use keyboard::KeyboardFocus;
+pub use keyboard::ShortcutBinding;
+pub use pad::PadAxis;
+
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
 
 use wayland_client::delegate_dispatch;
 
@@ -14,29 +40,62 @@ use wayland_client::WEnum;
 use wayland_client::protocol::wl_callback::WlCallback;
 use wayland_client::protocol::wl_compositor::WlCompositor;
 use wayland_client::protocol::wl_display::WlDisplay;
+use wayland_client::protocol::wl_keyboard::WlKeyboard;
+use wayland_client::protocol::wl_output::WlOutput;
 use wayland_client::protocol::wl_pointer::WlPointer;
 use wayland_client::protocol::wl_region::WlRegion;
 use wayland_client::protocol::wl_registry::WlRegistry;
 use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::protocol::wl_shm::WlShm;
 use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::protocol::wl_touch::WlTouch;
 
 use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::WpCursorShapeDeviceV1;
 use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_manager_v1::WpCursorShapeManagerV1;
 
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1;
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1;
+use wayland_protocols::wp::viewporter::client::wp_viewport::WpViewport;
+use wayland_protocols::wp::viewporter::client::wp_viewporter::WpViewporter;
+
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1;
+
+use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_manager_v1::ZxdgOutputManagerV1;
+use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_v1::ZxdgOutputV1;
+
+use wayland_protocols::xdg::shell::client::xdg_surface::XdgSurface;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::XdgToplevel;
+use wayland_protocols::xdg::shell::client::xdg_wm_base::XdgWmBase;
+
 use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1::ZwlrLayerShellV1;
 use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::ZwlrLayerSurfaceV1;
 
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_manager_v2::ZwpTabletManagerV2;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::ZwpTabletPadV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::ZwpTabletSeatV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::ZwpTabletToolV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_v2::ZwpTabletV2;
 
+use wayland_backend::client::ObjectId;
+
 use log::Level;
 use log::log;
 
+use chameleos::Button;
+use chameleos::Command;
+use chameleos::Destination;
+use chameleos::Edge;
+use chameleos::Preset;
+use chameleos::StrokeTransfer;
+use chameleos::Target;
+
 use crate::render::Backend;
-use crate::render::WgpuState;
 
 macro_rules! delegate_log {
     ($proxy:ty) => {
@@ -63,39 +122,46 @@ macro_rules! delegate_log {
 
 #[derive(Default)]
 struct SetupWaylandState {
-    force_backend: Option<Backend>,
-
     compositor: Option<WlCompositor>,
-    surface: Option<WlSurface>,
     seat: Option<WlSeat>,
+    shm: Option<WlShm>,
+    outputs: Vec<(u32, WlOutput)>,
+    xdg_output_manager: Option<ZxdgOutputManagerV1>,
 
     layer_shell: Option<ZwlrLayerShellV1>,
-    layer_surface: Option<ZwlrLayerSurfaceV1>,
+    xdg_wm_base: Option<XdgWmBase>,
+    fractional_scale_manager: Option<WpFractionalScaleManagerV1>,
+    viewporter: Option<WpViewporter>,
 
     cursor_shape_manager: Option<WpCursorShapeManagerV1>,
     tablet_manager: Option<ZwpTabletManagerV2>,
+    pointer_gestures: Option<ZwpPointerGesturesV1>,
 }
 
 impl SetupWaylandState {
-    fn new(force_backend: Option<Backend>) -> Self {
-        Self {
-            force_backend,
-            ..Default::default()
+    fn into_state(
+        self,
+        connection: Connection,
+        display: WlDisplay,
+    ) -> Result<WaylandState, String> {
+        let missing = |interface: &str| format!("compositor doesn't support {interface}");
+
+        if self.layer_shell.is_none() && self.xdg_wm_base.is_none() {
+            return Err(missing("zwlr_layer_shell_v1 or xdg_wm_base"));
         }
-    }
 
-    fn into_state(self, connection: Connection, display: WlDisplay) -> WaylandState {
-        WaylandState {
+        Ok(WaylandState {
             connection,
             display,
-            compositor: self.compositor.unwrap(),
-            surface: self.surface.unwrap(),
-            seat: self.seat.unwrap(),
-            layer_shell: self.layer_shell.unwrap(),
-            layer_surface: self.layer_surface.unwrap(),
-            cursor_shape_manager: self.cursor_shape_manager.unwrap(),
-            tablet_manager: self.tablet_manager.unwrap(),
-        }
+            compositor: self.compositor.ok_or_else(|| missing("wl_compositor"))?,
+            seat: self.seat.ok_or_else(|| missing("wl_seat"))?,
+            layer_shell: self.layer_shell,
+            xdg_wm_base: self.xdg_wm_base,
+            fractional_scale_manager: self.fractional_scale_manager,
+            viewporter: self.viewporter,
+            tablet_manager: self.tablet_manager,
+            pointer_gestures: self.pointer_gestures,
+        })
     }
 }
 
@@ -115,162 +181,804 @@ impl Dispatch<WlRegistry, QueueHandle<State>> for SetupWaylandState {
             Event::Global {
                 name,
                 interface,
-                version: _, // TODO should maybe use?
-            } => match interface.as_str() {
-                "wl_compositor" => {
-                    let compositor =
-                        registry.bind::<WlCompositor, _, _>(name, 5, state_qhandle, ());
-                    let surface = compositor.create_surface(state_qhandle, ());
-
-                    setup_state.compositor = Some(compositor);
-                    setup_state.surface = Some(surface);
-                }
-                "wl_seat" => {
-                    let seat = registry.bind::<WlSeat, _, _>(name, 9, state_qhandle, ());
-                    setup_state.seat = Some(seat);
+                version,
+            } => {
+                macro_rules! bind {
+                    ($supported:expr) => {
+                        bind_global(registry, name, version, $supported, state_qhandle)
+                    };
                 }
-                "zwlr_layer_shell_v1" => {
-                    let wl_surface = setup_state.surface.as_ref().unwrap();
-
-                    use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1::Layer;
-                    use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::Anchor;
-                    use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::KeyboardInteractivity;
-
-                    let layer_shell =
-                        registry.bind::<ZwlrLayerShellV1, _, _>(name, 4, state_qhandle, ());
-                    let layer_surface = layer_shell.get_layer_surface(
-                        &wl_surface,
-                        None, // TODO this sets the monitor we should spawn on
-                        Layer::Overlay,
-                        "chameleos".to_string(),
-                        state_qhandle,
-                        setup_state.force_backend,
-                    );
 
-                    layer_surface.set_anchor(Anchor::all());
-                    layer_surface.set_keyboard_interactivity(KeyboardInteractivity::None);
-                    layer_surface.set_exclusive_zone(-1);
-
-                    setup_state.layer_shell = Some(layer_shell);
-                    setup_state.layer_surface = Some(layer_surface);
-                }
-                "wp_cursor_shape_manager_v1" => {
-                    let cursor_shape_manager =
-                        registry.bind::<WpCursorShapeManagerV1, _, _>(name, 1, state_qhandle, ());
-                    setup_state.cursor_shape_manager = Some(cursor_shape_manager);
-                }
-                "zwp_tablet_manager_v2" => {
-                    let tablet_manager =
-                        registry.bind::<ZwpTabletManagerV2, _, _>(name, 1, state_qhandle, ());
-                    setup_state.tablet_manager = Some(tablet_manager);
+                match interface.as_str() {
+                    // preferred buffer scales and transforms were added in version 6
+                    "wl_compositor" => setup_state.compositor = Some(bind!(6)),
+                    // value120 scrolling was added in version 8
+                    "wl_seat" => setup_state.seat = Some(bind!(9)),
+                    "wl_shm" => setup_state.shm = Some(bind!(1)),
+                    // names and descriptions were added in version 4
+                    "wl_output" => setup_state.outputs.push((name, bind!(4))),
+                    "zxdg_output_manager_v1" => setup_state.xdg_output_manager = Some(bind!(3)),
+                    // on demand keyboard interactivity was added in version 4
+                    "zwlr_layer_shell_v1" => setup_state.layer_shell = Some(bind!(4)),
+                    "xdg_wm_base" => setup_state.xdg_wm_base = Some(bind!(1)),
+                    "wp_fractional_scale_manager_v1" => {
+                        setup_state.fractional_scale_manager = Some(bind!(1))
+                    }
+                    "wp_viewporter" => setup_state.viewporter = Some(bind!(1)),
+                    "wp_cursor_shape_manager_v1" => {
+                        setup_state.cursor_shape_manager = Some(bind!(1))
+                    }
+                    "zwp_tablet_manager_v2" => setup_state.tablet_manager = Some(bind!(1)),
+                    // hold gestures were added in version 3
+                    "zwp_pointer_gestures_v1" => setup_state.pointer_gestures = Some(bind!(3)),
+                    _ => {}
                 }
-                _ => {}
-            },
+            }
             Event::GlobalRemove { name: _ } => {}
             _ => {}
         }
     }
 }
 
+/// Binds a global at the highest version both the compositor and chameleos support
+///
+/// Features of later versions have to check `version()` before they are used.
+fn bind_global<I>(
+    registry: &WlRegistry,
+    name: u32,
+    advertised: u32,
+    supported: u32,
+    qhandle: &QueueHandle<State>,
+) -> I
+where
+    I: Proxy + 'static,
+    State: Dispatch<I, ()>,
+{
+    let version = advertised.min(supported);
+    if version < supported {
+        log!(
+            target: "chameleos::wayland",
+            Level::Info,
+            "{} is only available at version {version}",
+            I::interface().name,
+        );
+    }
+    registry.bind::<I, (), State>(name, version, qhandle, ())
+}
+
 pub struct State {
-    active: bool,
+    exit: bool,
 
     wayland: WaylandState,
-    draw: draw::DrawState,
+    overlays: overlay::Overlays,
+    stroke: draw::Stroke,
+    bindings: bindings::ButtonMap,
+    palette: palette::Palette,
 
+    input: input::ToolDispatcher,
     mouse: mouse::MouseState,
     tablet: tablet::TabletState,
+    pad: pad::PadState,
+    touch: touch::TouchState,
+    gesture: gesture::GestureState,
+    keyboard: keyboard::KeyboardState,
+    outputs: output::OutputState,
+    cursors: cursor::Cursors,
 
-    wgpu: Option<WgpuState>,
+    force_backend: Option<Backend>,
+    rescale_on_resize: bool,
+    keyboard_focus: KeyboardFocus,
+    /// how long overlays stay active without drawing, unless `chamel activate` says otherwise
+    timeout: Option<Duration>,
+    /// where the toolbar is docked, `None` while it is hidden
+    toolbar: Option<Edge>,
+    /// where the toolbar comes back after hiding it
+    toolbar_edge: Edge,
+    /// opens the radial menu while held
+    radial_button: Option<Button>,
+    indicator: IndicatorKind,
+    /// the outputs given with `--output`, empty for all of them
+    output_queries: Vec<String>,
+    /// lines left behind on an output that was unplugged or by `move-to-output --strokes stash`,
+    /// by output name
+    stashed: HashMap<String, draw::DrawState>,
 }
 
 impl State {
-    pub fn setup_wayland(cli: crate::Cli) -> (Self, EventQueue<Self>) {
+    pub fn setup_wayland(cli: crate::Cli) -> Result<(Self, EventQueue<Self>), String> {
         let connection = Connection::connect_to_env().unwrap();
         let mut setup_queue = connection.new_event_queue();
-        let event_queue = connection.new_event_queue();
+        let mut event_queue = connection.new_event_queue();
 
         let display = connection.display();
         let _registry = display.get_registry(&setup_queue.handle(), event_queue.handle());
 
-        let mut tmp_wayland_state = SetupWaylandState::new(cli.force_backend);
+        let mut tmp_wayland_state = SetupWaylandState::default();
 
         setup_queue.roundtrip(&mut tmp_wayland_state).unwrap();
 
-        let wayland_state = tmp_wayland_state.into_state(connection, display);
-        wayland_state.surface.frame(&event_queue.handle(), ());
-        wayland_state.surface.commit();
-
-        let state = Self {
-            active: false,
+        let outputs = output::OutputState::new(
+            std::mem::take(&mut tmp_wayland_state.outputs),
+            tmp_wayland_state.xdg_output_manager.take(),
+            &event_queue.handle(),
+        );
+        let cursors = cursor::Cursors::new(
+            &connection,
+            cli.cursor,
+            tmp_wayland_state.cursor_shape_manager.take(),
+            tmp_wayland_state.shm.take(),
+        );
+        let wayland_state = tmp_wayland_state.into_state(connection, display)?;
+        let timeout = cli
+            .timeout
+            .map(|seconds| {
+                Duration::try_from_secs_f32(seconds).map_err(|e| format!("invalid timeout: {e}"))
+            })
+            .transpose()?;
+
+        let mut state = Self {
+            exit: false,
             wayland: wayland_state,
-            draw: draw::DrawState::new(cli.stroke_width, cli.stroke_color),
-            mouse: mouse::MouseState::default(),
-            tablet: tablet::TabletState::default(),
-            wgpu: None,
+            overlays: overlay::Overlays::default(),
+            palette: palette::Palette::new(cli.palette, &cli.stroke_color),
+            stroke: draw::Stroke {
+                width: cli.stroke_width,
+                color: cli.stroke_color,
+            },
+            bindings: bindings::ButtonMap::new(&cli.bind),
+            input: input::ToolDispatcher::default(),
+            mouse: mouse::MouseState::new(cli.scroll_modifier),
+            tablet: tablet::TabletState::new(&cli.tablet_tool, &cli.stylus),
+            pad: pad::PadState::new(&cli.pad_button, cli.pad_ring, cli.pad_strip),
+            touch: touch::TouchState::new(cli.palm_rejection),
+            gesture: gesture::GestureState::new(&cli.gesture),
+            keyboard: keyboard::KeyboardState::new(&cli.key),
+            outputs,
+            cursors,
+            force_backend: cli.force_backend,
+            rescale_on_resize: cli.rescale_on_resize,
+            keyboard_focus: cli.keyboard,
+            timeout,
+            toolbar: cli.toolbar,
+            toolbar_edge: cli.toolbar.unwrap_or(Edge::Top),
+            radial_button: cli.radial_menu,
+            indicator: cli.indicator,
+            output_queries: cli.output,
+            stashed: HashMap::new(),
         };
 
-        (state, event_queue)
+        state.report_support();
+
+        if let Some(ref tablet_manager) = state.wayland.tablet_manager {
+            let tablet_seat =
+                tablet_manager.get_tablet_seat(&state.wayland.seat, &event_queue.handle(), ());
+            state.tablet.set_tablet_seat(tablet_seat);
+        }
+
+        // this registry stays around to notice outputs being plugged in and unplugged
+        state
+            .wayland
+            .display
+            .get_registry(&event_queue.handle(), ());
+
+        // output names only arrive once the outputs are bound
+        event_queue.roundtrip(&mut state).unwrap();
+
+        // every output gets an overlay, unless only some were asked for
+        let mut wl_outputs: Vec<WlOutput> = Vec::new();
+        if state.output_queries.is_empty() {
+            wl_outputs.extend(state.outputs.iter().map(|output| output.wl_output.clone()));
+        }
+        for query in &state.output_queries {
+            let wl_output = state.outputs.find(query)?.wl_output.clone();
+            if !wl_outputs.contains(&wl_output) {
+                wl_outputs.push(wl_output);
+            }
+        }
+
+        if wl_outputs.is_empty() {
+            log!(target: "chameleos::general", Level::Warn, "no outputs to show an overlay on");
+        }
+
+        for wl_output in wl_outputs {
+            state.create_overlay(
+                Some(wl_output),
+                draw::DrawState::default(),
+                false,
+                false,
+                &event_queue.handle(),
+            );
+        }
+
+        Ok((state, event_queue))
     }
 
-    pub fn toggle_input(&mut self, qhandle: &QueueHandle<Self>) {
-        if self.active {
-            self.deactivate(qhandle);
+    /// Logs which optional protocols the compositor supports and what is missing without them
+    fn report_support(&self) {
+        let log = |name: &str, support: &str| {
+            log!(
+                target: "chameleos::wayland",
+                Level::Info,
+                "compositor support for {name}: {support}"
+            );
+        };
+        let report = |name: &str, version: Option<u32>, without: &str| match version {
+            Some(version) => log(name, &format!("v{version}")),
+            None => log(name, &format!("missing, {without}")),
+        };
+
+        let wayland = &self.wayland;
+        report(
+            "layer shell",
+            wayland.layer_shell.as_ref().map(Proxy::version),
+            "using a fullscreen window, which may not stay on top",
+        );
+        let cursor_shape = if self.cursors.has_shapes() {
+            "yes"
+        } else if self.cursors.draws_brush() {
+            "missing, not needed for the brush"
+        } else if self.cursors.has_theme() {
+            "missing, using the XCursor theme"
         } else {
-            self.activate();
+            "missing, using the default cursor"
+        };
+        log("cursor shape", cursor_shape);
+        report(
+            "tablet",
+            wayland.tablet_manager.as_ref().map(Proxy::version),
+            "tablets only work as a mouse",
+        );
+        report(
+            "pointer gestures",
+            wayland.pointer_gestures.as_ref().map(Proxy::version),
+            "touchpad gestures are disabled",
+        );
+        report(
+            "fractional scale",
+            wayland
+                .fractional_scale_manager
+                .as_ref()
+                .filter(|_| wayland.viewporter.is_some())
+                .map(Proxy::version),
+            "only integer scales are used",
+        );
+    }
+
+    pub fn should_exit(&self) -> bool {
+        self.exit
+    }
+
+    pub fn execute(&mut self, command: Command, qhandle: &QueueHandle<Self>) {
+        self.execute_on(None, command, qhandle);
+    }
+
+    /// Runs a command on the overlays of some outputs, commands that aren't about
+    /// the overlays ignore the target
+    pub fn execute_on(
+        &mut self,
+        target: Option<&Target>,
+        command: Command,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        match command {
+            Command::Toggle => self.toggle_input(target, qhandle),
+            Command::Activate { once, timeout } => {
+                let timeout = match timeout.map(Duration::try_from_secs_f32) {
+                    None => self.timeout,
+                    Some(Ok(timeout)) => Some(timeout),
+                    Some(Err(e)) => {
+                        log!(target: "chameleos::general", Level::Warn, "invalid timeout: {e}");
+                        return;
+                    }
+                };
+                self.activate(target, once, timeout);
+            }
+            Command::Deactivate => self.deactivate(target, qhandle),
+            Command::Undo => self.undo(target),
+            Command::Redo => self.redo(target),
+            Command::Clear => self.clear(target),
+            Command::ClearAndDeactivate => {
+                self.clear(target);
+                self.deactivate(target, qhandle);
+            }
+            Command::StrokeWidth { width } => self.set_stroke_width(width),
+            Command::StrokeColor { color } => self.set_stroke_color(color),
+            Command::NextColor => self.next_color(),
+            Command::PreviousColor => self.previous_color(),
+            Command::Color { index } => self.pick_color(index),
+            Command::Bind { button, preset } => self.bind(button, preset),
+            Command::Stylus { preset } => self.set_stylus_preset(preset),
+            Command::MoveToOutput {
+                destination,
+                strokes,
+            } => self.move_to_output(target, &destination, strokes, qhandle),
+            Command::Hide => self.set_hidden(target, Some(true), qhandle),
+            Command::Show => self.set_hidden(target, Some(false), qhandle),
+            Command::ToggleVisibility => self.set_hidden(target, None, qhandle),
+            Command::Toolbar { edge } => self.set_toolbar(edge),
+            Command::Exit => self.exit = true,
+        }
+    }
+
+    /// The surfaces of the overlays a command applies to
+    ///
+    /// Without a target, commands about the last line (undo and redo) apply to the overlay that
+    /// was drawn on last and everything else to all overlays.
+    fn targets(&self, target: Option<&Target>, about_last_line: bool) -> Vec<ObjectId> {
+        match target {
+            None if about_last_line => self.overlays.last_drawn().cloned().into_iter().collect(),
+            None | Some(Target::All) => self
+                .overlays
+                .iter()
+                .map(|overlay| overlay.surface.id())
+                .collect(),
+            Some(Target::Output(query)) => match self.outputs.find(query) {
+                Ok(output) => self
+                    .overlays
+                    .iter()
+                    .filter(|overlay| overlay.output.as_ref() == Some(&output.wl_output))
+                    .map(|overlay| overlay.surface.id())
+                    .collect(),
+                Err(e) => {
+                    log!(target: "chameleos::general", Level::Warn, "{e}");
+                    Vec::new()
+                }
+            },
+        }
+    }
+
+    pub fn toggle_input(&mut self, target: Option<&Target>, qhandle: &QueueHandle<Self>) {
+        let any_active = self
+            .targets(target, false)
+            .iter()
+            .any(|surface| self.overlays.is_active(surface));
+
+        if any_active {
+            self.deactivate(target, qhandle);
+        } else {
+            self.activate(target, false, self.timeout);
         }
     }
 
-    pub fn activate(&mut self) {
-        // reset to full region
+    /// With `once` the overlays go back to passing input through after one line, with a timeout
+    /// after that long without drawing
+    pub fn activate(&mut self, target: Option<&Target>, once: bool, timeout: Option<Duration>) {
         log!(target: "chameleos::general", Level::Info, "activate");
-        self.wayland.surface.set_input_region(None);
-        self.wayland.surface.commit();
+        for surface in self.targets(target, false) {
+            if let Some(overlay) = self.overlays.get_mut(&surface) {
+                overlay.activate(self.keyboard_focus, once, timeout);
+            }
+        }
+    }
 
-        self.active = true;
+    /// The earliest time an activation timeout runs out, the event loop has to wake up by then
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.overlays
+            .iter()
+            .filter_map(overlay::Overlay::deadline)
+            .min()
+    }
+
+    /// Deactivates the overlays whose line was drawn or whose timeout ran out
+    pub fn expire_activations(&mut self, qhandle: &QueueHandle<Self>) {
+        let now = Instant::now();
+        let expired: Vec<ObjectId> = self
+            .overlays
+            .iter()
+            .filter(|overlay| overlay.expired(now))
+            .map(|overlay| overlay.surface.id())
+            .collect();
+
+        for surface in expired {
+            log!(target: "chameleos::general", Level::Info, "activation expired");
+            self.deactivate_overlay(&surface, qhandle);
+        }
     }
 
-    pub fn deactivate(&mut self, qhandle: &QueueHandle<Self>) {
+    pub fn deactivate(&mut self, target: Option<&Target>, qhandle: &QueueHandle<Self>) {
         log!(target: "chameleos::general", Level::Info, "deactivate");
-        let empty_region = self.wayland.compositor.create_region(qhandle, ());
-        self.wayland.surface.set_input_region(Some(&empty_region));
-        self.wayland.surface.commit();
+        for surface in self.targets(target, false) {
+            self.deactivate_overlay(&surface, qhandle);
+        }
+    }
+
+    /// Lets input through an overlay again, the lines drawn on it so far are finished since the
+    /// buttons, pens and fingers drawing them won't be let go of on it anymore
+    fn deactivate_overlay(&mut self, surface: &ObjectId, qhandle: &QueueHandle<Self>) {
+        let released = self.input.release(&mut self.overlays, surface);
+        if released.contains(&input::Device::Mouse) {
+            self.mouse.forget_held();
+        }
 
-        self.active = false;
+        if let Some(overlay) = self.overlays.get_mut(surface) {
+            overlay.deactivate(&self.wayland.compositor, qhandle);
+        }
     }
 
-    pub fn undo(&mut self) {
-        self.draw.undo();
+    /// Hides or shows the lines of overlays, `None` shows them if any of them are hidden
+    ///
+    /// Hidden overlays let input through, activating shows them again.
+    pub fn set_hidden(
+        &mut self,
+        target: Option<&Target>,
+        hidden: Option<bool>,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        let surfaces = self.targets(target, false);
+        let hidden = hidden.unwrap_or_else(|| {
+            !surfaces.iter().any(|surface| {
+                self.overlays
+                    .iter()
+                    .any(|overlay| overlay.surface.id() == *surface && overlay.is_hidden())
+            })
+        });
+
+        log!(target: "chameleos::general", Level::Info, "{}", if hidden { "hide" } else { "show" });
+        if hidden {
+            self.deactivate(target, qhandle);
+        }
+        for surface in surfaces {
+            if let Some(overlay) = self.overlays.get_mut(&surface) {
+                overlay.set_hidden(hidden);
+            }
+        }
     }
 
-    pub fn clear(&mut self) {
-        self.draw.clear();
+    pub fn undo(&mut self, target: Option<&Target>) {
+        for surface in self.targets(target, true) {
+            if let Some(draw) = self.overlays.draw_mut(&surface) {
+                draw.undo();
+            }
+        }
+    }
+
+    pub fn redo(&mut self, target: Option<&Target>) {
+        for surface in self.targets(target, true) {
+            if let Some(draw) = self.overlays.draw_mut(&surface) {
+                draw.redo();
+            }
+        }
+    }
+
+    pub fn clear(&mut self, target: Option<&Target>) {
+        for surface in self.targets(target, false) {
+            if let Some(draw) = self.overlays.draw_mut(&surface) {
+                draw.clear();
+            }
+        }
+    }
+
+    /// Recreates overlays on another output, taking their lines along unless they are stashed
+    pub fn move_to_output(
+        &mut self,
+        target: Option<&Target>,
+        destination: &Destination,
+        strokes: StrokeTransfer,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        let mut sources = self.targets(target, true);
+        if sources.is_empty() && target.is_none() {
+            sources.extend(
+                self.overlays
+                    .iter()
+                    .map(|overlay| overlay.surface.id())
+                    .take(1),
+            );
+        }
+
+        for source in sources {
+            let Some(overlay) = self.overlays.iter().find(|o| o.surface.id() == source) else {
+                continue;
+            };
+            let source_output = overlay.output.clone();
+
+            // None lets the compositor pick an output
+            let wl_output = match destination {
+                Destination::Focused => None,
+                Destination::Next => {
+                    let outputs: Vec<&WlOutput> = self
+                        .outputs
+                        .iter()
+                        .map(|output| &output.wl_output)
+                        .collect();
+                    let index = outputs
+                        .iter()
+                        .position(|&output| Some(output) == source_output.as_ref())
+                        .map_or(0, |index| index + 1);
+                    outputs.get(index % outputs.len().max(1)).cloned().cloned()
+                }
+                Destination::Output(query) => match self.outputs.find(query) {
+                    Ok(output) => Some(output.wl_output.clone()),
+                    Err(e) => {
+                        log!(target: "chameleos::general", Level::Warn, "{e}");
+                        return;
+                    }
+                },
+            };
+
+            if wl_output.is_some() && wl_output == source_output {
+                log!(target: "chameleos::general", Level::Info, "overlay is already on that output");
+                continue;
+            }
+
+            let active = self.overlays.is_active(&source);
+            let Some(mut overlay) = self.overlays.remove(&source) else {
+                continue;
+            };
+            let mut lines = std::mem::take(&mut overlay.draw);
+            let source_name = self.output_name(source_output.as_ref());
+            drop(overlay);
+
+            if strokes == StrokeTransfer::Stash {
+                match source_name {
+                    Some(name) => self.stash(name, std::mem::take(&mut lines)),
+                    None => log!(
+                        target: "chameleos::general",
+                        Level::Warn,
+                        "the output of the overlay is unknown, keeping its lines"
+                    ),
+                }
+            }
+            let rescale = strokes == StrokeTransfer::Rescale;
+
+            log!(target: "chameleos::general", Level::Info, "move overlay to {destination}");
+
+            // another overlay already covers the destination, its lines are joined
+            if let Some(ref wl_output) = wl_output
+                && let Some(existing) = self.overlays.by_output(wl_output)
+            {
+                let surface = existing.surface.id();
+                if let Some(draw) = self.overlays.draw_mut(&surface) {
+                    draw.merge(lines, rescale);
+                }
+                self.overlays.set_last_drawn(surface);
+                continue;
+            }
+
+            // lines stashed on the destination earlier come back
+            let draw = match self
+                .output_name(wl_output.as_ref())
+                .and_then(|name| self.stashed.remove(&name))
+            {
+                Some(mut stashed) => {
+                    stashed.merge(lines, rescale);
+                    stashed
+                }
+                None => lines,
+            };
+
+            let surface = self.create_overlay(wl_output, draw, rescale, active, qhandle);
+            self.overlays.set_last_drawn(surface);
+        }
+    }
+
+    fn create_overlay(
+        &mut self,
+        wl_output: Option<WlOutput>,
+        draw: draw::DrawState,
+        rescale: bool,
+        active: bool,
+        qhandle: &QueueHandle<Self>,
+    ) -> ObjectId {
+        let mut overlay = overlay::Overlay::new(
+            &self.wayland,
+            wl_output,
+            draw,
+            rescale,
+            self.force_backend,
+            qhandle,
+        );
+        if active {
+            overlay.activate(self.keyboard_focus, false, self.timeout);
+        }
+        let surface = overlay.surface.id();
+        self.overlays.push(overlay);
+        surface
+    }
+
+    /// When the window of an overlay is closed, e.g. from the compositor's window switcher
+    ///
+    /// The first time its lines are hidden and input goes through, closing it again while they are
+    /// hidden quits.
+    fn close_overlay(&mut self, surface: &ObjectId, qhandle: &QueueHandle<Self>) {
+        let Some(overlay) = self.overlays.get_mut(surface) else {
+            return;
+        };
+
+        if overlay.is_hidden() {
+            log!(target: "chameleos::general", Level::Info, "hidden overlay closed, exiting");
+            self.exit = true;
+            return;
+        }
+
+        log!(target: "chameleos::general", Level::Info, "hiding an overlay that was closed");
+        self.deactivate_overlay(surface, qhandle);
+        if let Some(overlay) = self.overlays.get_mut(surface) {
+            overlay.set_hidden(true);
+        }
+    }
+
+    /// Gives an overlay the compositor closed a new home
+    ///
+    /// While its output is still there the overlay opens on it again right away. If the output
+    /// went away and no other overlay is left its lines move to a remaining output, otherwise they
+    /// wait for the output to come back.
+    fn lose_overlay(&mut self, surface: &ObjectId, output_gone: bool, qhandle: &QueueHandle<Self>) {
+        let active = self.overlays.is_active(surface);
+        let last_drawn = self.overlays.last_drawn() == Some(surface);
+        let Some(mut overlay) = self.overlays.remove(surface) else {
+            return;
+        };
+        let lost_output = overlay.output.clone();
+        let lines = std::mem::take(&mut overlay.draw);
+        drop(overlay);
+
+        if !output_gone {
+            log!(target: "chameleos::general", Level::Info, "reopening an overlay that was closed");
+            let surface = self.create_overlay(lost_output, lines, false, active, qhandle);
+            if last_drawn {
+                self.overlays.set_last_drawn(surface);
+            }
+            return;
+        }
+
+        let remaining = self
+            .outputs
+            .iter()
+            .map(|output| &output.wl_output)
+            .find(|&wl_output| Some(wl_output) != lost_output.as_ref())
+            .cloned();
+
+        if self.overlays.iter().next().is_none()
+            && let Some(wl_output) = remaining
+        {
+            log!(target: "chameleos::general", Level::Info, "moving overlay to a remaining output");
+            let surface = self.create_overlay(Some(wl_output), lines, false, active, qhandle);
+            self.overlays.set_last_drawn(surface);
+            return;
+        }
+
+        match self.output_name(lost_output.as_ref()) {
+            Some(name) => {
+                log!(target: "chameleos::general", Level::Info, "keeping lines until {name} is back");
+                self.stash(name, lines);
+            }
+            None => {
+                log!(target: "chameleos::general", Level::Warn, "dropping lines of an unnamed output");
+            }
+        }
+    }
+
+    fn stash(&mut self, output_name: String, lines: draw::DrawState) {
+        match self.stashed.get_mut(&output_name) {
+            Some(stashed) => stashed.merge(lines, false),
+            None => {
+                self.stashed.insert(output_name, lines);
+            }
+        }
+    }
+
+    fn output_added(&mut self, wl_output: WlOutput, qhandle: &QueueHandle<Self>) {
+        if self.overlays.by_output(&wl_output).is_some() {
+            return;
+        }
+
+        let stashed = self
+            .output_name(Some(&wl_output))
+            .and_then(|name| self.stashed.remove(&name));
+
+        let wanted = self.output_queries.is_empty()
+            || self.output_queries.iter().any(|query| {
+                self.outputs
+                    .find(query)
+                    .is_ok_and(|output| output.wl_output == wl_output)
+            });
+
+        // an output that comes back gets its lines back, and anything is better than no overlay
+        if wanted || stashed.is_some() || self.overlays.iter().next().is_none() {
+            log!(target: "chameleos::general", Level::Info, "adding an overlay to a new output");
+            self.create_overlay(
+                Some(wl_output),
+                stashed.unwrap_or_default(),
+                false,
+                false,
+                qhandle,
+            );
+        }
+    }
+
+    fn output_removed(&mut self, wl_output: &WlOutput, qhandle: &QueueHandle<Self>) {
+        let lost: Vec<ObjectId> = self
+            .overlays
+            .iter()
+            .filter(|overlay| overlay.output.as_ref() == Some(wl_output))
+            .map(|overlay| overlay.surface.id())
+            .collect();
+        for surface in lost {
+            self.lose_overlay(&surface, true, qhandle);
+        }
+    }
+
+    fn output_name(&self, wl_output: Option<&WlOutput>) -> Option<String> {
+        self.outputs
+            .iter()
+            .find(|output| Some(&output.wl_output) == wl_output)
+            .and_then(|output| output.name.clone())
     }
 
     pub fn set_stroke_width(&mut self, width: f32) {
-        self.draw.set_stroke_width(width);
+        self.stroke.width = width;
+        self.tablet.remember_stroke(Some(width), None);
     }
 
     pub fn set_stroke_color(&mut self, color: csscolorparser::Color) {
-        self.draw.set_stroke_color(color);
+        self.palette.sync(&color);
+        self.tablet.remember_stroke(None, Some(color.clone()));
+        self.stroke.color = color;
+    }
+
+    pub fn next_color(&mut self) {
+        if let Some(color) = self.palette.next() {
+            self.tablet.remember_stroke(None, Some(color.clone()));
+            self.stroke.color = color;
+        }
+    }
+
+    pub fn previous_color(&mut self) {
+        if let Some(color) = self.palette.previous() {
+            self.tablet.remember_stroke(None, Some(color.clone()));
+            self.stroke.color = color;
+        }
     }
 
-    fn render(&mut self) {
-        if let Some(ref wgpu) = self.wgpu {
-            self.draw.render(wgpu);
+    pub fn pick_color(&mut self, index: usize) {
+        match self.palette.pick(index) {
+            Some(color) => {
+                self.tablet.remember_stroke(None, Some(color.clone()));
+                self.stroke.color = color;
+            }
+            None => log!(
+                target: "chameleos::general",
+                Level::Info,
+                "the palette has no color {index}"
+            ),
         }
     }
 
-    fn force_render(&mut self) {
-        if let Some(ref wgpu) = self.wgpu {
-            self.draw.force_render(wgpu);
+    /// Docks the toolbar to an edge, without one it is hidden or shown again
+    pub fn set_toolbar(&mut self, edge: Option<Edge>) {
+        self.toolbar = match (edge, self.toolbar) {
+            (Some(edge), _) => Some(edge),
+            (None, Some(_)) => None,
+            (None, None) => Some(self.toolbar_edge),
+        };
+        if let Some(edge) = self.toolbar {
+            self.toolbar_edge = edge;
         }
     }
+
+    fn ui_look(&self) -> ui::Look {
+        ui::Look {
+            stroke: self.stroke.clone(),
+            tool: self.bindings.get(Button::LEFT.0),
+            colors: self.palette.colors().to_vec(),
+        }
+    }
+
+    pub fn set_stylus_preset(&mut self, preset: Preset) {
+        self.tablet.set_stylus_preset(preset);
+    }
+
+    pub fn bind(&mut self, button: Button, preset: Preset) {
+        log!(target: "chameleos::general", Level::Info, "bind {button} to {preset}");
+        self.bindings.bind(button, &preset);
+    }
 }
 
 impl Drop for State {
     fn drop(&mut self) {
-        self.wgpu.take();
+        // the renderers need the connection to still be around
+        self.overlays.clear();
     }
 }
 
@@ -279,18 +987,24 @@ struct WaylandState {
     connection: Connection,
     display: WlDisplay,
     compositor: WlCompositor,
-    surface: WlSurface,
     seat: WlSeat,
 
-    layer_shell: ZwlrLayerShellV1,
-    layer_surface: ZwlrLayerSurfaceV1,
+    /// without layer shell, overlays are fullscreen windows
+    layer_shell: Option<ZwlrLayerShellV1>,
+    xdg_wm_base: Option<XdgWmBase>,
+    /// fractional scales need both, otherwise only integer scales are used
+    fractional_scale_manager: Option<WpFractionalScaleManagerV1>,
+    viewporter: Option<WpViewporter>,
 
-    cursor_shape_manager: WpCursorShapeManagerV1,
-    tablet_manager: ZwpTabletManagerV2,
+    /// tablets are optional
+    tablet_manager: Option<ZwpTabletManagerV2>,
+    /// touchpad gestures are optional
+    pointer_gestures: Option<ZwpPointerGesturesV1>,
 }
 
 delegate_log!(WlCompositor);
-delegate_log!(WlSurface);
+delegate_log!(WlShm);
+delegate_dispatch!(State: [WlSurface: ()] => overlay::Overlays);
 delegate_log!(WlRegion);
 
 impl Dispatch<WlSeat, ()> for State {
@@ -306,58 +1020,116 @@ impl Dispatch<WlSeat, ()> for State {
 
         use wayland_client::protocol::wl_seat::Capability;
         use wayland_client::protocol::wl_seat::Event;
-        match event {
-            Event::Capabilities { capabilities } => match capabilities {
-                WEnum::Value(capabilities) => {
-                    if capabilities.contains(Capability::Pointer) {
-                        let pointer = seat.get_pointer(qhandle, ());
-                        let device =
-                            state
-                                .wayland
-                                .cursor_shape_manager
-                                .get_pointer(&pointer, qhandle, ());
-                        state.mouse.set_cursor_shape_device(device);
-                    }
-                }
-                WEnum::Unknown(_) => {}
-            },
-            Event::Name { name: _ } => {
-                let tablet_seat = state
-                    .wayland
-                    .tablet_manager
-                    .get_tablet_seat(seat, qhandle, ());
-                state.tablet.set_tablet_seat(tablet_seat);
-            }
-            _ => {}
+        if let Event::Capabilities {
+            capabilities: WEnum::Value(capabilities),
+        } = event
+        {
+            let has_pointer = capabilities.contains(Capability::Pointer);
+            mouse::update_capability(state, seat, has_pointer, qhandle);
+
+            let has_touch = capabilities.contains(Capability::Touch);
+            touch::update_capability(state, seat, has_touch, qhandle);
+
+            let has_keyboard = capabilities.contains(Capability::Keyboard)
+                && state.keyboard_focus != KeyboardFocus::None;
+            state
+                .keyboard
+                .update_capability(seat, has_keyboard, qhandle);
         }
     }
 }
 
-impl Dispatch<WlCallback, ()> for State {
+impl Dispatch<WlCallback, ObjectId> for State {
     fn event(
         state: &mut Self,
         _callback: &WlCallback,
         event: <WlCallback as Proxy>::Event,
-        _data: &(),
+        surface: &ObjectId,
         _conn: &Connection,
         qhandle: &QueueHandle<Self>,
     ) {
         log!(target: "chameleos::wayland", Level::Trace, "WlCallback: {:?}", event);
 
         use wayland_client::protocol::wl_callback::Event;
-        match event {
-            Event::Done { callback_data: _ } => {
-                state.render();
+        if let Event::Done { callback_data: _ } = event {
+            let look = state.ui_look();
+            let Some(overlay) = state.overlays.get_mut(surface) else {
+                return;
+            };
+
+            overlay.update_indicator(state.indicator, &look);
+            overlay.update_toolbar(state.toolbar, &look);
+            overlay.render();
+
+            overlay.surface.frame(qhandle, surface.clone());
+            overlay.surface.commit();
+        }
+    }
+}
 
-                state.wayland.surface.frame(qhandle, ());
-                state.wayland.surface.commit();
+/// Work that waits until the compositor handled everything sent before a `wl_display.sync`
+enum AfterSync {
+    /// the layer surface of an overlay was closed, if that was because its output went away the
+    /// registry said so by now
+    ReopenOverlay(ObjectId),
+    /// an output too old to send `done` was plugged in
+    FinishOutput(ObjectId),
+}
+
+impl Dispatch<WlCallback, AfterSync> for State {
+    fn event(
+        state: &mut Self,
+        _callback: &WlCallback,
+        event: <WlCallback as Proxy>::Event,
+        after_sync: &AfterSync,
+        _conn: &Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "WlCallback: {:?}", event);
+
+        use wayland_client::protocol::wl_callback::Event;
+        let Event::Done { callback_data: _ } = event else {
+            return;
+        };
+
+        match after_sync {
+            AfterSync::ReopenOverlay(surface) => {
+                // gone already if it moved on with its output
+                let Some(overlay) = state.overlays.get_mut(surface) else {
+                    return;
+                };
+                let output_gone = overlay.output.as_ref().is_some_and(|wl_output| {
+                    !state
+                        .outputs
+                        .iter()
+                        .any(|output| output.wl_output == *wl_output)
+                });
+                state.lose_overlay(surface, output_gone, qhandle);
+            }
+            AfterSync::FinishOutput(wl_output) => {
+                // unless it was unplugged again
+                if let Some(wl_output) = state.outputs.finish_pending(wl_output) {
+                    state.output_added(wl_output, qhandle);
+                }
             }
-            _ => {}
         }
     }
 }
 
+delegate_dispatch!(State: [WlRegistry: ()] => output::OutputState);
+delegate_dispatch!(State: [WlOutput: ()] => output::OutputState);
+delegate_log!(ZxdgOutputManagerV1);
+delegate_dispatch!(State: [ZxdgOutputV1: ObjectId] => output::OutputState);
+
+delegate_log!(WpFractionalScaleManagerV1);
+delegate_dispatch!(State: [WpFractionalScaleV1: ObjectId] => overlay::Overlays);
+delegate_log!(WpViewporter);
+delegate_log!(WpViewport);
+
 delegate_log!(ZwlrLayerShellV1);
+delegate_dispatch!(State: [XdgWmBase: ()] => overlay::Overlays);
+delegate_dispatch!(State: [XdgSurface: ()] => overlay::Overlays);
+delegate_dispatch!(State: [XdgToplevel: ()] => overlay::Overlays);
 impl Dispatch<ZwlrLayerSurfaceV1, Option<Backend>> for State {
     fn event(
         state: &mut Self,
@@ -365,7 +1137,7 @@ impl Dispatch<ZwlrLayerSurfaceV1, Option<Backend>> for State {
         event: <ZwlrLayerSurfaceV1 as Proxy>::Event,
         force_backend: &Option<Backend>,
         _conn: &Connection,
-        _qhandle: &QueueHandle<Self>,
+        qhandle: &QueueHandle<Self>,
     ) {
         log!(target: "chameleos::wayland", Level::Info, "LayerSurface: {:?}", event);
 
@@ -378,40 +1150,52 @@ impl Dispatch<ZwlrLayerSurfaceV1, Option<Backend>> for State {
             } => {
                 layer_surface.ack_configure(serial);
 
-                if state.wgpu.is_none() {
-                    state.draw.set_height(height);
-
-                    let wgpu = WgpuState::new(
+                if let Some(overlay) = state.overlays.by_shell_surface_mut(&layer_surface.id()) {
+                    overlay.configure(
                         &state.wayland.display,
-                        &state.wayland.surface,
                         width,
                         height,
                         *force_backend,
+                        state.rescale_on_resize,
                     );
-
-                    if wgpu.surface_config().alpha_mode == wgpu::CompositeAlphaMode::PreMultiplied {
-                        state.draw.set_pre_multiply_stroke_color(true);
-                    }
-
-                    state.wgpu = Some(wgpu);
-
-                    // some compositors are unhappy if we don't force render here
-                    state.force_render();
                 }
             }
-            Event::Closed => {}
+            // the output may be going away, which the registry only tells after this, so the
+            // overlay waits for the compositor to catch up before it opens again
+            Event::Closed => {
+                let surface = state
+                    .overlays
+                    .by_shell_surface_mut(&layer_surface.id())
+                    .map(|overlay| overlay.surface.id());
+                if let Some(surface) = surface {
+                    state
+                        .wayland
+                        .display
+                        .sync(qhandle, AfterSync::ReopenOverlay(surface));
+                }
+            }
             _ => {}
         }
     }
 }
 
 delegate_dispatch!(State: [WlPointer: ()] => mouse::MouseState);
+delegate_dispatch!(State: [WlTouch: ()] => touch::TouchState);
+delegate_dispatch!(State: [WlKeyboard: ()] => keyboard::KeyboardState);
 
 delegate_log!(WpCursorShapeManagerV1);
 delegate_log!(WpCursorShapeDeviceV1);
 
+delegate_log!(ZwpPointerGesturesV1);
+delegate_dispatch!(State: [ZwpPointerGestureSwipeV1: ()] => gesture::GestureState);
+delegate_dispatch!(State: [ZwpPointerGesturePinchV1: ()] => gesture::GestureState);
+delegate_dispatch!(State: [ZwpPointerGestureHoldV1: ()] => gesture::GestureState);
+
 delegate_log!(ZwpTabletManagerV2);
 delegate_dispatch!(State: [ZwpTabletSeatV2: ()] => tablet::TabletState);
 delegate_log!(ZwpTabletV2);
 delegate_dispatch!(State: [ZwpTabletToolV2: ()] => tablet::TabletState);
-delegate_log!(ZwpTabletPadV2);
+delegate_dispatch!(State: [ZwpTabletPadV2: ()] => pad::PadState);
+delegate_dispatch!(State: [ZwpTabletPadGroupV2: ()] => pad::PadState);
+delegate_dispatch!(State: [ZwpTabletPadRingV2: ()] => pad::PadState);
+delegate_dispatch!(State: [ZwpTabletPadStripV2: ()] => pad::PadState);
@@ -1,3 +1,5 @@
+use wayland_backend::client::ObjectId;
+
 use wayland_client::Connection;
 use wayland_client::Dispatch;
 use wayland_client::Proxy;
@@ -5,142 +7,296 @@ use wayland_client::QueueHandle;
 use wayland_client::WEnum;
 
 use wayland_client::protocol::wl_pointer::WlPointer;
-
-use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
-use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::WpCursorShapeDeviceV1;
+use wayland_client::protocol::wl_seat::WlSeat;
 
 use log::Level;
 use log::log;
 
-#[inline(always)]
-pub fn draw_pos(
-    pressed: bool,
-    motion: Option<(f64, f64)>,
-    held: bool,
-    pos: Option<(f64, f64)>,
-) -> Option<(f64, f64)> {
-    held.then_some(motion)
-        .flatten()
-        .or_else(|| pressed.then_some(pos).flatten())
-}
+use chameleos::Button;
+
+use super::cursor::Cursor;
+use super::input::Device;
+use super::input::Sample;
+use super::input::ToolEvent;
+use super::pad::PadAxis;
+
+/// Scrolling this far on a touchpad counts as one notch of a scroll wheel
+const SCROLL_DISTANCE: f64 = 15.0;
 
-#[derive(Default)]
 pub struct MouseState {
     event_sequence: EventSequence,
 
-    cursor_shape_device: Option<WpCursorShapeDeviceV1>,
+    /// the seat's pointer, if it has one
+    pointer: Option<WlPointer>,
+    cursor: Option<Cursor>,
 
+    /// the overlay the pointer is on
+    surface: Option<ObjectId>,
     mouse_pos: Option<(f64, f64)>,
-    left_button_held: bool,
-    right_button_held: bool,
+    /// the button that started the current stroke
+    held: Option<u32>,
+
+    /// while this button is held, scrolling cycles through the palette instead
+    scroll_modifier: Button,
+    scroll_modifier_held: bool,
+    /// scrolled notches that didn't add up to a whole step yet
+    scrolled: f64,
 }
 
 impl MouseState {
-    pub fn set_cursor_shape_device(&mut self, cursor_shape_device: WpCursorShapeDeviceV1) {
-        self.cursor_shape_device = Some(cursor_shape_device)
+    pub fn new(scroll_modifier: Button) -> Self {
+        Self {
+            event_sequence: EventSequence::default(),
+            pointer: None,
+            cursor: None,
+            surface: None,
+            mouse_pos: None,
+            held: None,
+            scroll_modifier,
+            scroll_modifier_held: false,
+            scrolled: 0.0,
+        }
     }
 
-    fn update_state(&mut self, sequence: EventSequence) {
-        if let Some(new_pos) = sequence.motion {
-            self.mouse_pos = Some(new_pos);
-        }
+    /// Forgets the button that started the current stroke, once the stroke ended without it
+    pub fn forget_held(&mut self) {
+        self.held = None;
+    }
 
-        if let Some(_) = sequence.leave_serial {
-            self.mouse_pos = None;
+    /// Returns the number of whole notches scrolled up in this frame
+    fn scroll_steps(&mut self, sequence: &EventSequence) -> i32 {
+        // high resolution wheels report fractions of a notch in value120,
+        // touchpads only report a distance
+        if let Some(value120) = sequence.scroll_value120 {
+            self.scrolled += value120 as f64 / 120.0;
+        } else if let Some(value) = sequence.scroll {
+            self.scrolled += value / SCROLL_DISTANCE;
         }
 
-        if sequence.left_button_pressed {
-            self.left_button_held = true;
-        }
-        if sequence.left_button_released {
-            self.left_button_held = false;
+        let steps = self.scrolled.trunc();
+        self.scrolled -= steps;
+
+        if sequence.scroll_stop {
+            self.scrolled = 0.0;
         }
 
-        if sequence.right_button_pressed {
-            self.right_button_held = true;
+        // scrolling down is positive, but scrolling up should increase
+        -(steps as i32)
+    }
+}
+
+/// Gets the seat's pointer along with its cursor and gestures when it shows up and lets go of
+/// them once it's gone, seats announce their capabilities again whenever any device comes or goes
+pub fn update_capability(
+    state: &mut super::State,
+    seat: &WlSeat,
+    has_pointer: bool,
+    qhandle: &QueueHandle<super::State>,
+) {
+    let mouse = &mut state.mouse;
+    match (has_pointer, mouse.pointer.take()) {
+        (true, None) => {
+            let pointer = seat.get_pointer(qhandle, ());
+            mouse.cursor = state
+                .cursors
+                .for_pointer(&pointer, &state.wayland.compositor, qhandle);
+            if let Some(ref pointer_gestures) = state.wayland.pointer_gestures {
+                state.gesture.attach(&pointer, pointer_gestures, qhandle);
+            }
+            mouse.pointer = Some(pointer);
         }
-        if sequence.right_button_released {
-            self.right_button_held = false;
+        (false, Some(pointer)) => {
+            state.gesture.detach();
+            if let Some(cursor) = mouse.cursor.take() {
+                cursor.destroy();
+            }
+            // releasing was added in version 3
+            if pointer.version() >= 3 {
+                pointer.release();
+            }
+
+            // a button that is still held won't be let go of anymore
+            if mouse.held.take().is_some() {
+                state
+                    .input
+                    .dispatch(&mut state.overlays, Device::Mouse, ToolEvent::Up);
+            }
+            mouse.surface = None;
+            mouse.mouse_pos = None;
+            mouse.scroll_modifier_held = false;
         }
+        (_, pointer) => mouse.pointer = pointer,
     }
 }
 
 impl Dispatch<WlPointer, (), super::State> for MouseState {
     fn event(
         state: &mut super::State,
-        _pointer: &WlPointer,
+        pointer: &WlPointer,
         event: <WlPointer as Proxy>::Event,
         _data: &(),
         _conn: &Connection,
-        _qhandle: &QueueHandle<super::State>,
+        qhandle: &QueueHandle<super::State>,
     ) {
         log!(target: "chameleos::wayland", Level::Debug, "WlPointer: {:?}", event);
 
         let mouse = &mut state.mouse;
-        let draw = &mut state.draw;
 
-        if let Some(sequence) = mouse.event_sequence.dispatch(event) {
-            mouse.update_state(sequence);
+        // frames were added in version 5, before that every event stands on its own
+        let sequence = if pointer.version() >= 5 {
+            mouse.event_sequence.dispatch(event)
+        } else {
+            mouse.event_sequence.dispatch(event);
+            Some(std::mem::take(&mut mouse.event_sequence))
+        };
+        let Some(sequence) = sequence else {
+            return;
+        };
 
-            if let Some(ref device) = mouse.cursor_shape_device
-                && let Some(serial) = sequence.enter_serial
-            {
-                device.set_shape(serial, Shape::Crosshair);
-            }
+        if let Some(ref cursor) = mouse.cursor
+            && let Some(serial) = sequence.enter_serial
+        {
+            cursor.set_for_pointer(pointer, serial);
+        }
 
-            let pen_pos = draw_pos(
-                sequence.left_button_pressed,
-                sequence.motion,
-                mouse.left_button_held,
-                mouse.mouse_pos,
-            );
+        if let Some(ref surface) = sequence.enter_surface {
+            mouse.surface = Some(surface.clone());
+        }
 
-            if let Some(pos) = pen_pos {
-                draw.add_point_to_line(pos);
-            }
+        if let Some(new_pos) = sequence.motion {
+            mouse.mouse_pos = Some(new_pos);
+        }
 
-            let erase_pos = draw_pos(
-                sequence.right_button_pressed,
-                sequence.motion,
-                mouse.right_button_held,
-                mouse.mouse_pos,
-            );
+        if sequence.button_pressed == Some(mouse.scroll_modifier.0) {
+            mouse.scroll_modifier_held = true;
+        }
+        if sequence.button_released == Some(mouse.scroll_modifier.0) {
+            mouse.scroll_modifier_held = false;
+        }
 
-            if let Some(pos) = erase_pos {
-                draw.erase(pos);
+        let buttons = (sequence.button_pressed, sequence.button_released);
+        let (surface, pos) = (mouse.surface.clone(), mouse.mouse_pos);
+        let in_menu = super::radial::steer(
+            state,
+            Device::Mouse,
+            buttons,
+            surface.as_ref(),
+            pos,
+            qhandle,
+        );
+
+        let mouse = &mut state.mouse;
+        let overlays = &mut state.overlays;
+        let input = &mut state.input;
+
+        if let Some(button) = sequence.button_released
+            && mouse.held == Some(button)
+        {
+            mouse.held = None;
+            input.dispatch(overlays, Device::Mouse, ToolEvent::Up);
+        }
+
+        // from a button pressed on the toolbar
+        let mut commands = Vec::new();
+        if !in_menu
+            && let Some(button) = sequence.button_pressed
+            && mouse.held.is_none()
+            && let Some(tool) = state.bindings.get(button)
+            && let Some(pos) = mouse.mouse_pos
+            && let Some(ref surface) = mouse.surface
+        {
+            mouse.held = Some(button);
+            let event = ToolEvent::Down {
+                surface: surface.clone(),
+                tool,
+                stroke: state.bindings.stroke(button, &state.stroke),
+                sample: Sample::at(pos),
+            };
+            commands = input.dispatch(overlays, Device::Mouse, event);
+        } else if !in_menu && let Some(pos) = sequence.motion {
+            let event = ToolEvent::Move {
+                sample: Sample::at(pos),
+            };
+            input.dispatch(overlays, Device::Mouse, event);
+        }
+
+        if sequence.leave_serial.is_some() {
+            // the button will be let go of somewhere we don't hear about it
+            if mouse.held.take().is_some() {
+                input.dispatch(overlays, Device::Mouse, ToolEvent::Up);
             }
+            mouse.surface = None;
+            mouse.mouse_pos = None;
+            mouse.scroll_modifier_held = false;
+        }
 
-            if sequence.left_button_released {
-                draw.cut_line();
+        let steps = mouse.scroll_steps(&sequence);
+        if steps != 0
+            && let Some(surface) = mouse.surface.clone()
+            && overlays.is_active(&surface)
+        {
+            let axis = if mouse.scroll_modifier_held {
+                PadAxis::Color
+            } else {
+                PadAxis::Width
+            };
+            let pos = mouse.mouse_pos;
+
+            super::pad::run_steps(state, axis, steps, qhandle);
+
+            if let Some(pos) = pos
+                && let Some(draw) = state.overlays.draw_mut(&surface)
+            {
+                draw.show_preview(pos, &state.stroke);
             }
         }
+
+        for command in commands {
+            state.execute(command, qhandle);
+        }
+
+        if state.cursors.draws_brush() {
+            let mouse = &state.mouse;
+            let button = mouse.held.unwrap_or(Button::LEFT.0);
+            let hover = mouse.surface.as_ref().zip(mouse.mouse_pos);
+            let stroke = state.bindings.stroke(button, &state.stroke);
+            state
+                .overlays
+                .move_brush(Device::Mouse, hover, state.bindings.get(button), &stroke);
+        }
     }
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone)]
 struct EventSequence {
     motion: Option<(f64, f64)>,
 
-    left_button_pressed: bool,
-    left_button_released: bool,
-    right_button_pressed: bool,
-    right_button_released: bool,
+    button_pressed: Option<u32>,
+    button_released: Option<u32>,
 
     enter_serial: Option<u32>,
+    enter_surface: Option<ObjectId>,
     leave_serial: Option<u32>,
+
+    /// vertical scrolling only
+    scroll: Option<f64>,
+    scroll_value120: Option<i32>,
+    scroll_stop: bool,
 }
 
 impl EventSequence {
     fn dispatch(&mut self, event: <WlPointer as Proxy>::Event) -> Option<Self> {
+        use wayland_client::protocol::wl_pointer::Axis;
         use wayland_client::protocol::wl_pointer::Event;
         match event {
             Event::Enter {
                 serial,
-                surface: _,
+                surface,
                 surface_x,
                 surface_y,
             } => {
                 self.enter_serial = Some(serial);
+                self.enter_surface = Some(surface.id());
                 self.motion = Some((surface_x, surface_y));
                 None
             }
@@ -164,25 +320,36 @@ impl EventSequence {
             } => {
                 use wayland_client::protocol::wl_pointer::ButtonState;
 
-                // left mouse button
-                if button == 272 {
-                    match button_state {
-                        WEnum::Value(ButtonState::Released) => self.left_button_released = true,
-                        WEnum::Value(ButtonState::Pressed) => self.left_button_pressed = true,
-                        _ => {}
-                    }
-                }
-
-                if button == 273 {
-                    match button_state {
-                        WEnum::Value(ButtonState::Released) => self.right_button_released = true,
-                        WEnum::Value(ButtonState::Pressed) => self.right_button_pressed = true,
-                        _ => {}
-                    }
+                match button_state {
+                    WEnum::Value(ButtonState::Released) => self.button_released = Some(button),
+                    WEnum::Value(ButtonState::Pressed) => self.button_pressed = Some(button),
+                    _ => {}
                 }
 
                 None
             }
+            Event::Axis {
+                time: _,
+                axis: WEnum::Value(Axis::VerticalScroll),
+                value,
+            } => {
+                *self.scroll.get_or_insert(0.0) += value;
+                None
+            }
+            Event::AxisValue120 {
+                axis: WEnum::Value(Axis::VerticalScroll),
+                value120,
+            } => {
+                *self.scroll_value120.get_or_insert(0) += value120;
+                None
+            }
+            Event::AxisStop {
+                time: _,
+                axis: WEnum::Value(Axis::VerticalScroll),
+            } => {
+                self.scroll_stop = true;
+                None
+            }
             Event::Frame => {
                 let mut tmp = Self::default();
                 std::mem::swap(self, &mut tmp);
@@ -0,0 +1,293 @@
+use wayland_backend::client::ObjectId;
+
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::Proxy;
+use wayland_client::QueueHandle;
+
+use wayland_client::WEnum;
+
+use wayland_client::protocol::wl_output::Transform;
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::protocol::wl_registry::WlRegistry;
+
+use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_manager_v1::ZxdgOutputManagerV1;
+use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_v1::ZxdgOutputV1;
+
+use log::Level;
+use log::log;
+
+/// A monitor, as far as we know it
+pub struct Output {
+    pub wl_output: WlOutput,
+    /// where older compositors send the name and description
+    xdg_output: Option<ZxdgOutputV1>,
+    /// the name of the global in the registry, needed to notice when it goes away
+    global_name: u32,
+    /// plugged in after startup and not done describing itself yet
+    pending: bool,
+
+    /// the connector name, e.g. `DP-1`
+    pub name: Option<String>,
+    /// usually make, model and connector, e.g. `Dell Inc. DELL U2720Q (DP-1)`
+    pub description: Option<String>,
+    /// the integer scale, only needed when the compositor can't tell surfaces directly
+    pub scale: i32,
+    /// how the output is rotated, also only needed for older compositors
+    pub transform: Transform,
+}
+
+impl std::fmt::Display for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name.as_deref().unwrap_or("<unnamed>"))?;
+        if let Some(ref description) = self.description {
+            write!(f, " ({description})")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct OutputState {
+    outputs: Vec<Output>,
+    xdg_output_manager: Option<ZxdgOutputManagerV1>,
+}
+
+impl OutputState {
+    /// Names only arrive with `wl_output` version 4, so older compositors need `xdg_output`
+    pub fn new(
+        wl_outputs: Vec<(u32, WlOutput)>,
+        xdg_output_manager: Option<ZxdgOutputManagerV1>,
+        qhandle: &QueueHandle<super::State>,
+    ) -> Self {
+        let mut output_state = Self {
+            outputs: Vec::new(),
+            xdg_output_manager,
+        };
+        for (global_name, wl_output) in wl_outputs {
+            output_state.add(global_name, wl_output, false, qhandle);
+        }
+        output_state
+    }
+
+    fn add(
+        &mut self,
+        global_name: u32,
+        wl_output: WlOutput,
+        pending: bool,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        let xdg_output = self
+            .xdg_output_manager
+            .as_ref()
+            .map(|manager| manager.get_xdg_output(&wl_output, qhandle, wl_output.id()));
+
+        self.outputs.push(Output {
+            wl_output,
+            xdg_output,
+            global_name,
+            pending,
+            name: None,
+            description: None,
+            scale: 1,
+            transform: Transform::Normal,
+        });
+    }
+
+    fn remove(&mut self, global_name: u32) -> Option<Output> {
+        let index = self
+            .outputs
+            .iter()
+            .position(|output| output.global_name == global_name)?;
+        Some(self.outputs.remove(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Output> {
+        self.outputs.iter()
+    }
+
+    /// Finds an output by its connector name, or else by part of its description
+    pub fn find(&self, query: &str) -> Result<&Output, String> {
+        if let Some(output) = self.outputs.iter().find(|output| {
+            output
+                .name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(query))
+        }) {
+            return Ok(output);
+        }
+
+        let lowercase_query = query.to_lowercase();
+        let matches: Vec<&Output> = self
+            .outputs
+            .iter()
+            .filter(|output| {
+                output.description.as_deref().is_some_and(|description| {
+                    description.to_lowercase().contains(&lowercase_query)
+                })
+            })
+            .collect();
+
+        match matches.as_slice() {
+            [output] => Ok(output),
+            [] => Err(format!(
+                "unknown output \"{query}\", available outputs are:{}",
+                list(self.outputs.iter())
+            )),
+            _ => Err(format!(
+                "output \"{query}\" is ambiguous, it matches:{}",
+                list(matches.into_iter())
+            )),
+        }
+    }
+
+    fn get_mut(&mut self, id: &ObjectId) -> Option<&mut Output> {
+        self.outputs
+            .iter_mut()
+            .find(|output| output.wl_output.id() == *id)
+    }
+
+    /// Stops waiting for a plugged in output to describe itself, returns it unless that happened
+    /// already
+    pub fn finish_pending(&mut self, id: &ObjectId) -> Option<WlOutput> {
+        let output = self.get_mut(id)?;
+        std::mem::take(&mut output.pending).then(|| output.wl_output.clone())
+    }
+}
+
+fn list<'a>(outputs: impl Iterator<Item = &'a Output>) -> String {
+    outputs.map(|output| format!("\n    {output}")).collect()
+}
+
+impl Dispatch<WlOutput, (), super::State> for OutputState {
+    fn event(
+        state: &mut super::State,
+        wl_output: &WlOutput,
+        event: <WlOutput as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "WlOutput: {:?}", event);
+
+        let Some(output) = state.outputs.get_mut(&wl_output.id()) else {
+            return;
+        };
+
+        use wayland_client::protocol::wl_output::Event;
+        match event {
+            Event::Name { name } => output.name = Some(name),
+            Event::Description { description } => output.description = Some(description),
+            Event::Scale { factor } => output.scale = factor,
+            Event::Geometry {
+                transform: WEnum::Value(transform),
+                ..
+            } => output.transform = transform,
+            Event::Done if output.pending => {
+                output.pending = false;
+                state.output_added(wl_output.clone(), qhandle);
+            }
+            // e.g. the output was rotated
+            Event::Done => state.overlays.follow_output(output),
+            _ => {}
+        }
+    }
+}
+
+/// Watches for outputs being plugged in or unplugged after startup
+impl Dispatch<WlRegistry, (), super::State> for OutputState {
+    fn event(
+        state: &mut super::State,
+        registry: &WlRegistry,
+        event: <WlRegistry as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "WlRegistry: {:?}", event);
+
+        use wayland_client::protocol::wl_registry::Event;
+        match event {
+            Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == "wl_output" => {
+                // the outputs from startup are announced again
+                if state
+                    .outputs
+                    .iter()
+                    .any(|output| output.global_name == name)
+                {
+                    return;
+                }
+
+                // names and descriptions were added in version 4
+                let wl_output: WlOutput = super::bind_global(registry, name, version, 4, qhandle);
+                let (id, sends_done) = (wl_output.id(), wl_output.version() >= 2);
+                state.outputs.add(name, wl_output, true, qhandle);
+                // before version 2 there is no `done`, so everything it and its xdg output
+                // describe it with has arrived once the compositor caught up
+                if !sends_done {
+                    let finish = super::AfterSync::FinishOutput(id);
+                    state.wayland.display.sync(qhandle, finish);
+                }
+            }
+            Event::GlobalRemove { name } => {
+                let Some(output) = state
+                    .outputs
+                    .iter()
+                    .find(|output| output.global_name == name)
+                else {
+                    return;
+                };
+
+                log!(target: "chameleos::general", Level::Info, "output {output} was removed");
+
+                let wl_output = output.wl_output.clone();
+                state.output_removed(&wl_output, qhandle);
+
+                if let Some(xdg_output) = state
+                    .outputs
+                    .remove(name)
+                    .and_then(|output| output.xdg_output)
+                {
+                    xdg_output.destroy();
+                }
+                if wl_output.version() >= 3 {
+                    wl_output.release();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZxdgOutputV1, ObjectId, super::State> for OutputState {
+    fn event(
+        state: &mut super::State,
+        _xdg_output: &ZxdgOutputV1,
+        event: <ZxdgOutputV1 as Proxy>::Event,
+        wl_output_id: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "ZxdgOutputV1: {:?}", event);
+
+        let Some(output) = state.outputs.get_mut(wl_output_id) else {
+            return;
+        };
+
+        // wl_output reports the same, whichever comes first is fine
+        use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_v1::Event;
+        match event {
+            Event::Name { name } => {
+                output.name.get_or_insert(name);
+            }
+            Event::Description { description } => {
+                output.description.get_or_insert(description);
+            }
+            _ => {}
+        }
+    }
+}
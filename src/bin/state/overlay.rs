@@ -0,0 +1,672 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use wayland_backend::client::ObjectId;
+
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::Proxy;
+use wayland_client::QueueHandle;
+
+use wayland_client::WEnum;
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_display::WlDisplay;
+
+use wayland_client::protocol::wl_output::Transform;
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1;
+use wayland_protocols::wp::viewporter::client::wp_viewport::WpViewport;
+
+use log::Level;
+use log::log;
+
+use chameleos::Command;
+use chameleos::Edge;
+use chameleos::Tool;
+
+use super::brush::Brush;
+use super::draw::DrawState;
+use super::draw::Stroke;
+use super::indicator::Indicator;
+use super::indicator::IndicatorKind;
+use super::input::Device;
+use super::keyboard::KeyboardFocus;
+use super::radial::RadialMenu;
+use super::shell::ShellSurface;
+use super::toolbar::Toolbar;
+use super::ui::Look;
+
+use crate::render::Backend;
+use crate::render::Geometry;
+use crate::render::SurfaceSize;
+use crate::render::WgpuState;
+
+/// A surface covering one output, with its own renderer and lines
+pub struct Overlay {
+    /// unknown until the surface enters an output when the compositor picked it
+    pub output: Option<WlOutput>,
+    pub surface: WlSurface,
+    pub shell: ShellSurface,
+    viewport: Option<WpViewport>,
+    fractional_scale: Option<WpFractionalScaleV1>,
+
+    /// the logical size lines are drawn in, from the last configure
+    size: Option<(u32, u32)>,
+    /// in 120ths, the way fractional scales are reported
+    scale: u32,
+    /// the buffer is rendered rotated like the output, so the compositor can show it as is
+    transform: Transform,
+
+    active: bool,
+    /// goes back to passing input through after the next line, see `chamel activate --once`
+    once: bool,
+    /// goes back to passing input through after this long without drawing
+    timeout: Option<Duration>,
+    /// a line was finished since activating
+    finished_line: bool,
+    last_input: Instant,
+    /// nothing is shown while hidden, the lines stay for showing them again
+    hidden: bool,
+    /// built for the size of the overlay, while input is active
+    indicator: Option<Indicator>,
+    /// built for the size of the overlay, while input is active
+    toolbar: Option<Toolbar>,
+    radial: Option<RadialMenu>,
+    /// one for each device hovering this overlay, with `--cursor brush`
+    brushes: Vec<Brush>,
+    pub draw: DrawState,
+    /// whether the lines are stretched to the size of the first configure, after moving here
+    /// from another output
+    rescale_lines: bool,
+    wgpu: Option<WgpuState>,
+}
+
+impl Overlay {
+    pub fn new(
+        wayland: &super::WaylandState,
+        output: Option<WlOutput>,
+        draw: DrawState,
+        rescale_lines: bool,
+        force_backend: Option<Backend>,
+        qhandle: &QueueHandle<super::State>,
+    ) -> Self {
+        let surface = wayland.compositor.create_surface(qhandle, ());
+        let shell = ShellSurface::new(wayland, &surface, output.as_ref(), force_backend, qhandle);
+
+        // the buffer is rendered at the physical size and scaled down by the viewport
+        let (viewport, fractional_scale) =
+            match (&wayland.viewporter, &wayland.fractional_scale_manager) {
+                (Some(viewporter), Some(manager)) => (
+                    Some(viewporter.get_viewport(&surface, qhandle, ())),
+                    Some(manager.get_fractional_scale(&surface, qhandle, surface.id())),
+                ),
+                _ => (None, None),
+            };
+
+        // don't take any input until activated
+        let empty_region = wayland.compositor.create_region(qhandle, ());
+        surface.set_input_region(Some(&empty_region));
+
+        surface.frame(qhandle, surface.id());
+        surface.commit();
+
+        Self {
+            output,
+            surface,
+            shell,
+            viewport,
+            fractional_scale,
+            size: None,
+            scale: 120,
+            transform: Transform::Normal,
+            active: false,
+            once: false,
+            timeout: None,
+            finished_line: false,
+            last_input: Instant::now(),
+            hidden: false,
+            indicator: None,
+            toolbar: None,
+            radial: None,
+            brushes: Vec::new(),
+            draw,
+            rescale_lines,
+            wgpu: None,
+        }
+    }
+
+    pub fn activate(
+        &mut self,
+        keyboard_focus: KeyboardFocus,
+        once: bool,
+        timeout: Option<Duration>,
+    ) {
+        // reset to full region
+        self.surface.set_input_region(None);
+        self.shell.set_keyboard_focus(keyboard_focus);
+        self.surface.commit();
+
+        // drawing on lines nobody sees makes no sense
+        self.set_hidden(false);
+
+        self.active = true;
+        self.once = once;
+        self.timeout = timeout;
+        self.finished_line = false;
+        self.last_input = Instant::now();
+    }
+
+    pub fn deactivate(&mut self, compositor: &WlCompositor, qhandle: &QueueHandle<super::State>) {
+        let empty_region = compositor.create_region(qhandle, ());
+        self.surface.set_input_region(Some(&empty_region));
+        self.shell.set_keyboard_focus(KeyboardFocus::None);
+        self.surface.commit();
+
+        self.radial = None;
+        self.brushes.clear();
+        self.active = false;
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        if hidden == self.hidden {
+            return;
+        }
+
+        self.hidden = hidden;
+        self.force_render();
+    }
+
+    /// When the timeout of the activation runs out
+    pub fn deadline(&self) -> Option<Instant> {
+        self.timeout
+            .filter(|_| self.active)
+            .map(|timeout| self.last_input + timeout)
+    }
+
+    /// Whether an activation with `once` or a timeout is over
+    pub fn expired(&self, now: Instant) -> bool {
+        (self.active && self.once && self.finished_line)
+            || self.deadline().is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Keeps a timeout from running out
+    pub fn note_input(&mut self) {
+        self.last_input = Instant::now();
+    }
+
+    /// Ends the line of a device, which is all an activation with `once` waits for
+    pub fn finish_line(&mut self, device: Device) {
+        self.draw.cut_line(device);
+        self.finished_line = true;
+    }
+
+    /// Sets up the renderer once the compositor told us how large the overlay is, and adapts it
+    /// whenever the size changes after that
+    pub fn configure(
+        &mut self,
+        display: &WlDisplay,
+        width: u32,
+        height: u32,
+        force_backend: Option<Backend>,
+        rescale_on_resize: bool,
+    ) {
+        // the size is up to us, but anchoring to all edges should never leave it open
+        if width == 0 || height == 0 || self.size == Some((width, height)) {
+            return;
+        }
+        self.size = Some((width, height));
+        // laid out for the old size, the next frame builds them again
+        self.indicator = None;
+        self.toolbar = None;
+
+        if let Some(ref viewport) = self.viewport {
+            viewport.set_destination(width as i32, height as i32);
+        }
+
+        if self.wgpu.is_some() {
+            log!(target: "chameleos::render", Level::Info, "overlay resized to {width}x{height}");
+
+            self.draw.resize(width, height, rescale_on_resize);
+            self.resize_buffer();
+            return;
+        }
+
+        log!(target: "chameleos::render", Level::Info, "overlay of {width}x{height}");
+
+        self.draw.resize(width, height, self.rescale_lines);
+
+        let wgpu = WgpuState::new(
+            display,
+            &self.surface,
+            self.surface_size(width, height),
+            force_backend,
+        );
+
+        if wgpu.surface_config().alpha_mode == wgpu::CompositeAlphaMode::PreMultiplied {
+            self.draw.set_pre_multiply_stroke_color(true);
+        }
+
+        self.wgpu = Some(wgpu);
+
+        // some compositors are unhappy if we don't force render here
+        self.force_render();
+    }
+
+    /// Takes a scale in 120ths, integer scales are only used without fractional scaling
+    pub fn set_scale(&mut self, scale: u32) {
+        // buffer scales were added in version 3 of wl_surface
+        if scale == 0
+            || scale == self.scale
+            || (self.viewport.is_none() && self.surface.version() < 3)
+        {
+            return;
+        }
+
+        log!(target: "chameleos::render", Level::Info, "overlay scale {}", scale as f64 / 120.0);
+
+        self.scale = scale;
+        if self.viewport.is_none() {
+            self.surface.set_buffer_scale((scale / 120).max(1) as i32);
+        }
+        self.resize_buffer();
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        // buffer transforms were added in version 2 of wl_surface
+        if transform == self.transform || self.surface.version() < 2 {
+            return;
+        }
+
+        log!(target: "chameleos::render", Level::Info, "overlay transform {transform:?}");
+
+        self.transform = transform;
+        self.surface.set_buffer_transform(transform);
+        self.resize_buffer();
+    }
+
+    /// Without `wl_surface` version 6 the compositor doesn't tell us the preferred scale and
+    /// transform, so the ones of the output are used
+    pub fn follow_output(&mut self, output: &super::output::Output) {
+        if self.surface.version() >= 6 {
+            return;
+        }
+
+        if self.fractional_scale.is_none() {
+            self.set_scale(output.scale.max(1) as u32 * 120);
+        }
+        self.set_transform(output.transform);
+    }
+
+    fn surface_size(&self, width: u32, height: u32) -> SurfaceSize {
+        // rounded half away from zero, as the fractional scale protocol asks for
+        let buffer = (
+            (width * self.scale + 60) / 120,
+            (height * self.scale + 60) / 120,
+        );
+
+        let sideways = matches!(
+            self.transform,
+            Transform::_90 | Transform::_270 | Transform::Flipped90 | Transform::Flipped270
+        );
+
+        SurfaceSize {
+            buffer: if sideways {
+                (buffer.1, buffer.0)
+            } else {
+                buffer
+            },
+            screen: (width, height),
+            transform: self.transform,
+        }
+    }
+
+    fn resize_buffer(&mut self) {
+        let Some((width, height)) = self.size else {
+            return;
+        };
+        let size = self.surface_size(width, height);
+
+        if let Some(ref mut wgpu) = self.wgpu {
+            wgpu.resize(size);
+            self.force_render();
+        }
+    }
+
+    /// Builds the indicator again if it looks different now, it only shows while input is active
+    pub fn update_indicator(&mut self, kind: IndicatorKind, look: &Look) {
+        let kind = if self.active {
+            kind
+        } else {
+            IndicatorKind::None
+        };
+        let up_to_date = match self.indicator {
+            Some(ref indicator) => indicator.is_built_for(kind, look),
+            None => kind == IndicatorKind::None,
+        };
+        if up_to_date {
+            return;
+        }
+
+        self.indicator = self
+            .size
+            .and_then(|size| Indicator::new(kind, look.clone(), size, &self.draw));
+        self.draw.set_changed();
+    }
+
+    /// Builds the toolbar again if it looks different now, it only shows while input is active
+    pub fn update_toolbar(&mut self, edge: Option<Edge>, look: &Look) {
+        let edge = edge.filter(|_| self.active);
+        let up_to_date = match (&self.toolbar, edge) {
+            (Some(toolbar), Some(edge)) => toolbar.is_built_for(edge, look),
+            (None, None) => true,
+            _ => false,
+        };
+        if up_to_date {
+            return;
+        }
+
+        self.toolbar = match (edge, self.size) {
+            (Some(edge), Some(size)) => Some(Toolbar::new(edge, look.clone(), size, &self.draw)),
+            _ => None,
+        };
+        self.draw.set_changed();
+    }
+
+    pub fn open_radial(&mut self, device: Device, pos: (f64, f64), look: Look) {
+        self.radial = Some(RadialMenu::new(device, pos, look, &self.draw));
+        self.draw.set_changed();
+    }
+
+    pub fn point_radial(&mut self, pos: (f64, f64)) {
+        if let Some(ref mut radial) = self.radial
+            && radial.point_at(pos, &self.draw)
+        {
+            self.draw.set_changed();
+        }
+    }
+
+    /// Returns the commands of the slice that was picked
+    pub fn close_radial(&mut self) -> Vec<Command> {
+        let commands = self
+            .radial
+            .take()
+            .map(|radial| radial.commands())
+            .unwrap_or_default();
+        self.draw.set_changed();
+        commands
+    }
+
+    /// Moves the brush of a device to a position, or takes it away with `None`
+    ///
+    /// On the toolbar or while the device has the radial menu open the brush turns into a plain
+    /// dot, the buttons and slices are what it points at then.
+    fn set_brush(&mut self, device: Device, at: Option<((f64, f64), Tool, &Stroke)>) {
+        let had_brush = self.brushes.iter().any(|brush| brush.device() == device);
+        self.brushes.retain(|brush| brush.device() != device);
+
+        let in_radial = self
+            .radial
+            .as_ref()
+            .is_some_and(|radial| radial.device() == device);
+        let brush = at.filter(|_| self.active).map(|(pos, tool, stroke)| {
+            let on_toolbar = self
+                .toolbar
+                .as_ref()
+                .is_some_and(|toolbar| toolbar.hit(pos).is_some());
+            if in_radial || on_toolbar {
+                Brush::pointer(device, pos, &self.draw)
+            } else {
+                Brush::new(device, pos, tool, stroke, &self.draw)
+            }
+        });
+
+        if !had_brush && brush.is_none() {
+            return;
+        }
+        self.brushes.extend(brush);
+        self.draw.set_changed();
+    }
+
+    pub fn render(&mut self) {
+        // the empty frame from hiding stays up
+        if self.hidden {
+            return;
+        }
+
+        if let Some(ref wgpu) = self.wgpu {
+            self.draw.render(
+                wgpu,
+                &ui(&self.indicator, &self.toolbar, &self.radial, &self.brushes),
+            );
+        }
+    }
+
+    pub fn force_render(&mut self) {
+        if let Some(ref wgpu) = self.wgpu {
+            if self.hidden {
+                wgpu.render([]);
+                return;
+            }
+
+            self.draw.force_render(
+                wgpu,
+                &ui(&self.indicator, &self.toolbar, &self.radial, &self.brushes),
+            );
+        }
+    }
+}
+
+/// What is drawn on top of the lines
+fn ui<'a>(
+    indicator: &'a Option<Indicator>,
+    toolbar: &'a Option<Toolbar>,
+    radial: &'a Option<RadialMenu>,
+    brushes: &'a [Brush],
+) -> Vec<&'a Geometry> {
+    indicator
+        .iter()
+        .map(Indicator::geometry)
+        .chain(toolbar.iter().map(Toolbar::geometry))
+        .chain(radial.iter().map(RadialMenu::geometry))
+        .chain(brushes.iter().map(Brush::geometry))
+        .collect()
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        // the renderer has to go before the surface it renders to
+        self.wgpu.take();
+        if let Some(ref fractional_scale) = self.fractional_scale {
+            fractional_scale.destroy();
+        }
+        if let Some(ref viewport) = self.viewport {
+            viewport.destroy();
+        }
+        self.shell.destroy();
+        self.surface.destroy();
+    }
+}
+
+#[derive(Default)]
+pub struct Overlays {
+    overlays: Vec<Overlay>,
+    /// undo and redo apply to this overlay unless told otherwise
+    last_drawn: Option<ObjectId>,
+}
+
+impl Overlays {
+    pub fn push(&mut self, overlay: Overlay) {
+        self.overlays.push(overlay);
+    }
+
+    pub fn remove(&mut self, surface: &ObjectId) -> Option<Overlay> {
+        let index = self
+            .overlays
+            .iter()
+            .position(|overlay| overlay.surface.id() == *surface)?;
+        if self.last_drawn.as_ref() == Some(surface) {
+            self.last_drawn = None;
+        }
+        Some(self.overlays.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.overlays.clear();
+        self.last_drawn = None;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Overlay> {
+        self.overlays.iter()
+    }
+
+    pub fn get_mut(&mut self, surface: &ObjectId) -> Option<&mut Overlay> {
+        self.overlays
+            .iter_mut()
+            .find(|overlay| overlay.surface.id() == *surface)
+    }
+
+    /// Called when an output changed its scale or transform
+    pub fn follow_output(&mut self, output: &super::output::Output) {
+        for overlay in self.overlays.iter_mut() {
+            if overlay.output.as_ref() == Some(&output.wl_output) {
+                overlay.follow_output(output);
+            }
+        }
+    }
+
+    pub fn by_output(&self, output: &WlOutput) -> Option<&Overlay> {
+        self.overlays
+            .iter()
+            .find(|overlay| overlay.output.as_ref() == Some(output))
+    }
+
+    pub fn by_shell_surface_mut(&mut self, shell_surface: &ObjectId) -> Option<&mut Overlay> {
+        self.overlays
+            .iter_mut()
+            .find(|overlay| overlay.shell.id() == *shell_surface)
+    }
+
+    pub fn draw_mut(&mut self, surface: &ObjectId) -> Option<&mut DrawState> {
+        self.get_mut(surface).map(|overlay| &mut overlay.draw)
+    }
+
+    /// The overlay with the radial menu a device opened
+    pub fn with_radial_mut(&mut self, device: Device) -> Option<&mut Overlay> {
+        self.overlays.iter_mut().find(|overlay| {
+            overlay
+                .radial
+                .as_ref()
+                .is_some_and(|radial| radial.device() == device)
+        })
+    }
+
+    /// Draws the brush of a device on the overlay it hovers and takes it off all others,
+    /// `None` when it left the overlays or has no tool to draw with
+    pub fn move_brush(
+        &mut self,
+        device: Device,
+        hover: Option<(&ObjectId, (f64, f64))>,
+        tool: Option<Tool>,
+        stroke: &Stroke,
+    ) {
+        for overlay in self.overlays.iter_mut() {
+            let at = match (hover, tool) {
+                (Some((surface, pos)), Some(tool)) if overlay.surface.id() == *surface => {
+                    Some((pos, tool, stroke))
+                }
+                _ => None,
+            };
+            overlay.set_brush(device, at);
+        }
+    }
+
+    /// The commands of the toolbar button at a position, `None` if it isn't on the toolbar
+    pub fn toolbar_hit(&self, surface: &ObjectId, pos: (f64, f64)) -> Option<Vec<Command>> {
+        self.overlays
+            .iter()
+            .find(|overlay| overlay.surface.id() == *surface)?
+            .toolbar
+            .as_ref()?
+            .hit(pos)
+    }
+
+    pub fn is_active(&self, surface: &ObjectId) -> bool {
+        self.overlays
+            .iter()
+            .any(|overlay| overlay.surface.id() == *surface && overlay.active)
+    }
+
+    pub fn any_active(&self) -> bool {
+        self.overlays.iter().any(|overlay| overlay.active)
+    }
+
+    pub fn last_drawn(&self) -> Option<&ObjectId> {
+        self.last_drawn.as_ref()
+    }
+
+    pub fn set_last_drawn(&mut self, surface: ObjectId) {
+        self.last_drawn = Some(surface);
+    }
+}
+
+impl Dispatch<WlSurface, (), super::State> for Overlays {
+    fn event(
+        state: &mut super::State,
+        surface: &WlSurface,
+        event: <WlSurface as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "WlSurface: {:?}", event);
+
+        let Some(overlay) = state.overlays.get_mut(&surface.id()) else {
+            return;
+        };
+
+        // fractional scales take precedence, then the scale the compositor prefers for the
+        // surface and before version 6 the scale of the output it's on, same for transforms
+        use wayland_client::protocol::wl_surface::Event;
+        match event {
+            Event::Enter { output } => {
+                if let Some(known) = state.outputs.iter().find(|known| known.wl_output == output) {
+                    overlay.follow_output(known);
+                }
+                overlay.output.get_or_insert(output);
+            }
+            Event::PreferredBufferScale { factor } if overlay.fractional_scale.is_none() => {
+                overlay.set_scale(factor.max(1) as u32 * 120);
+            }
+            Event::PreferredBufferTransform {
+                transform: WEnum::Value(transform),
+            } => overlay.set_transform(transform),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WpFractionalScaleV1, ObjectId, super::State> for Overlays {
+    fn event(
+        state: &mut super::State,
+        _fractional_scale: &WpFractionalScaleV1,
+        event: <WpFractionalScaleV1 as Proxy>::Event,
+        surface: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "WpFractionalScaleV1: {:?}", event);
+
+        use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::Event;
+        if let Event::PreferredScale { scale } = event
+            && let Some(overlay) = state.overlays.get_mut(surface)
+        {
+            overlay.set_scale(scale);
+        }
+    }
+}
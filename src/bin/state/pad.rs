@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+
+use wayland_backend::client::ObjectId;
+
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::Proxy;
+use wayland_client::QueueHandle;
+use wayland_client::WEnum;
+
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::ZwpTabletPadV2;
+
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::EVT_RING_OPCODE;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::EVT_GROUP_OPCODE;
+
+use clap::ValueEnum;
+
+use log::Level;
+use log::log;
+
+use chameleos::Command;
+use chameleos::PadButtonBinding;
+
+/// Degrees a ring has to be turned for one step
+const RING_STEP: f64 = 15.0;
+/// Strip positions are normalized to 0..=65535, so this is a 16th of the strip
+const STRIP_STEP: f64 = 4096.0;
+
+/// What turning a ring or sliding along a strip does
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum PadAxis {
+    Width,
+    Color,
+    None,
+}
+
+impl PadAxis {
+    pub fn command(&self, step: i32, stroke_width: f32) -> Option<Command> {
+        match self {
+            PadAxis::Width => Some(Command::StrokeWidth {
+                width: (stroke_width + step as f32).max(1.0),
+            }),
+            PadAxis::Color if step > 0 => Some(Command::NextColor),
+            PadAxis::Color => Some(Command::PreviousColor),
+            PadAxis::None => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct AxisState {
+    last: Option<f64>,
+    pending: Option<f64>,
+    stopped: bool,
+    accumulated: f64,
+}
+
+impl AxisState {
+    /// Returns the number of whole steps moved since the last frame
+    fn frame(&mut self, step_size: f64, wrap: Option<f64>) -> i32 {
+        let mut steps = 0;
+
+        if let Some(value) = self.pending.take() {
+            if let Some(last) = self.last {
+                let mut delta = value - last;
+                // rings report absolute angles, so going past 0 shouldn't count as a full turn
+                if let Some(wrap) = wrap {
+                    if delta > wrap / 2.0 {
+                        delta -= wrap;
+                    } else if delta < -wrap / 2.0 {
+                        delta += wrap;
+                    }
+                }
+
+                self.accumulated += delta;
+                steps = (self.accumulated / step_size).trunc() as i32;
+                self.accumulated -= steps as f64 * step_size;
+            }
+            self.last = Some(value);
+        }
+
+        if self.stopped {
+            self.last = None;
+            self.accumulated = 0.0;
+            self.stopped = false;
+        }
+
+        steps
+    }
+}
+
+/// What a pad brought along, to let go of once the pad is removed
+#[derive(Default)]
+struct PadChildren {
+    groups: Vec<ZwpTabletPadGroupV2>,
+    rings: Vec<ZwpTabletPadRingV2>,
+    strips: Vec<ZwpTabletPadStripV2>,
+}
+
+pub struct PadState {
+    button_commands: HashMap<u32, Command>,
+    ring_axis: PadAxis,
+    strip_axis: PadAxis,
+
+    /// by the pad they belong to
+    children: HashMap<ObjectId, PadChildren>,
+    rings: HashMap<ObjectId, AxisState>,
+    strips: HashMap<ObjectId, AxisState>,
+}
+
+impl PadState {
+    pub fn new(
+        button_bindings: &[PadButtonBinding],
+        ring_axis: PadAxis,
+        strip_axis: PadAxis,
+    ) -> Self {
+        Self {
+            button_commands: button_bindings
+                .iter()
+                .map(|binding| (binding.button, binding.command.clone()))
+                .collect(),
+            ring_axis,
+            strip_axis,
+            children: HashMap::new(),
+            rings: HashMap::new(),
+            strips: HashMap::new(),
+        }
+    }
+
+    /// The children of the pad a group belongs to
+    fn children_of_group(&mut self, group: &ZwpTabletPadGroupV2) -> Option<&mut PadChildren> {
+        self.children
+            .values_mut()
+            .find(|children| children.groups.contains(group))
+    }
+
+    /// Destroys the groups, rings and strips of a pad, before the pad itself
+    fn remove(&mut self, pad: &ZwpTabletPadV2) {
+        if let Some(children) = self.children.remove(&pad.id()) {
+            for ring in children.rings {
+                self.rings.remove(&ring.id());
+                ring.destroy();
+            }
+            for strip in children.strips {
+                self.strips.remove(&strip.id());
+                strip.destroy();
+            }
+            for group in children.groups {
+                group.destroy();
+            }
+        }
+        pad.destroy();
+    }
+}
+
+/// Runs the command of an axis once per step
+pub(super) fn run_steps(
+    state: &mut super::State,
+    axis: PadAxis,
+    steps: i32,
+    qhandle: &QueueHandle<super::State>,
+) {
+    for _ in 0..steps.abs() {
+        if let Some(command) = axis.command(steps.signum(), state.stroke.width) {
+            state.execute(command, qhandle);
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletPadV2, (), super::State> for PadState {
+    fn event(
+        state: &mut super::State,
+        pad: &ZwpTabletPadV2,
+        event: <ZwpTabletPadV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "ZwpTabletPadV2: {:?}", event);
+
+        use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::ButtonState;
+        use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::Event;
+        match event {
+            Event::Button {
+                time: _,
+                button,
+                state: WEnum::Value(ButtonState::Pressed),
+            } => {
+                if let Some(command) = state.pad.button_commands.get(&button).cloned() {
+                    state.execute(command, qhandle);
+                }
+            }
+            Event::Group { pad_group } => {
+                let children = state.pad.children.entry(pad.id()).or_default();
+                children.groups.push(pad_group);
+            }
+            Event::Removed => state.pad.remove(pad),
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(super::State, ZwpTabletPadV2, [
+        EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, ()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadGroupV2, (), super::State> for PadState {
+    fn event(
+        state: &mut super::State,
+        group: &ZwpTabletPadGroupV2,
+        event: <ZwpTabletPadGroupV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "ZwpTabletPadGroupV2: {:?}", event);
+
+        let Some(children) = state.pad.children_of_group(group) else {
+            return;
+        };
+
+        use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::Event;
+        match event {
+            Event::Ring { ring } => children.rings.push(ring),
+            Event::Strip { strip } => children.strips.push(strip),
+            _ => {}
+        }
+    }
+
+    wayland_client::event_created_child!(super::State, ZwpTabletPadGroupV2, [
+        EVT_RING_OPCODE => (ZwpTabletPadRingV2, ()),
+        EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, ()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadRingV2, (), super::State> for PadState {
+    fn event(
+        state: &mut super::State,
+        ring: &ZwpTabletPadRingV2,
+        event: <ZwpTabletPadRingV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "ZwpTabletPadRingV2: {:?}", event);
+
+        let axis_state = state.pad.rings.entry(ring.id()).or_default();
+
+        use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_ring_v2::Event;
+        match event {
+            Event::Angle { degrees } => axis_state.pending = Some(degrees),
+            Event::Stop => axis_state.stopped = true,
+            Event::Frame { time: _ } => {
+                let steps = axis_state.frame(RING_STEP, Some(360.0));
+                run_steps(state, state.pad.ring_axis, steps, qhandle);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletPadStripV2, (), super::State> for PadState {
+    fn event(
+        state: &mut super::State,
+        strip: &ZwpTabletPadStripV2,
+        event: <ZwpTabletPadStripV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "ZwpTabletPadStripV2: {:?}", event);
+
+        let axis_state = state.pad.strips.entry(strip.id()).or_default();
+
+        use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_strip_v2::Event;
+        match event {
+            Event::Position { position } => axis_state.pending = Some(position as f64),
+            Event::Stop => axis_state.stopped = true,
+            Event::Frame { time: _ } => {
+                // strip positions grow downwards, sliding up should increase
+                let steps = -axis_state.frame(STRIP_STEP, None);
+                run_steps(state, state.pad.strip_axis, steps, qhandle);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn move_to(axis: &mut AxisState, value: f64, wrap: Option<f64>) -> i32 {
+        axis.pending = Some(value);
+        axis.frame(10.0, wrap)
+    }
+
+    #[test]
+    fn counts_whole_steps_and_keeps_the_rest() {
+        let mut axis = AxisState::default();
+        // the first frame only tells where the finger is
+        assert_eq!(move_to(&mut axis, 100.0, None), 0);
+        assert_eq!(move_to(&mut axis, 125.0, None), 2);
+        assert_eq!(move_to(&mut axis, 131.0, None), 1);
+        assert_eq!(move_to(&mut axis, 112.0, None), -1);
+        assert_eq!(axis.frame(10.0, None), 0);
+    }
+
+    #[test]
+    fn starts_over_after_stopping() {
+        let mut axis = AxisState::default();
+        move_to(&mut axis, 100.0, None);
+        assert_eq!(move_to(&mut axis, 105.0, None), 0);
+        axis.stopped = true;
+        assert_eq!(axis.frame(10.0, None), 0);
+        assert_eq!(move_to(&mut axis, 10.0, None), 0);
+        assert_eq!(move_to(&mut axis, 15.0, None), 0);
+    }
+
+    #[test]
+    fn rings_turn_across_zero() {
+        let mut axis = AxisState::default();
+        move_to(&mut axis, 355.0, Some(360.0));
+        assert_eq!(move_to(&mut axis, 15.0, Some(360.0)), 2);
+        assert_eq!(move_to(&mut axis, 345.0, Some(360.0)), -3);
+    }
+}
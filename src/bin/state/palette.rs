@@ -0,0 +1,100 @@
+pub struct Palette {
+    colors: Vec<csscolorparser::Color>,
+    /// `None` while the stroke color isn't one of the palette colors
+    index: Option<usize>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<csscolorparser::Color>, stroke_color: &csscolorparser::Color) -> Self {
+        let index = colors.iter().position(|color| color == stroke_color);
+        Self { colors, index }
+    }
+
+    pub fn next(&mut self) -> Option<csscolorparser::Color> {
+        if self.colors.is_empty() {
+            return None;
+        }
+
+        let index = self.index.map_or(0, |i| (i + 1) % self.colors.len());
+        self.index = Some(index);
+        Some(self.colors[index].clone())
+    }
+
+    pub fn previous(&mut self) -> Option<csscolorparser::Color> {
+        if self.colors.is_empty() {
+            return None;
+        }
+
+        let len = self.colors.len();
+        let index = self.index.map_or(len - 1, |i| (i + len - 1) % len);
+        self.index = Some(index);
+        Some(self.colors[index].clone())
+    }
+
+    pub fn colors(&self) -> &[csscolorparser::Color] {
+        &self.colors
+    }
+
+    /// Counting from 1, like the number keys
+    pub fn pick(&mut self, number: usize) -> Option<csscolorparser::Color> {
+        let index = number.checked_sub(1)?;
+        let color = self.colors.get(index)?.clone();
+        self.index = Some(index);
+        Some(color)
+    }
+
+    /// Keeps track of colors set from outside the palette
+    pub fn sync(&mut self, stroke_color: &csscolorparser::Color) {
+        self.index = self.colors.iter().position(|color| color == stroke_color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette(stroke_color: &str) -> Palette {
+        let colors = ["#ff0000", "#00ff00", "#0000ff"]
+            .map(|color| csscolorparser::parse(color).unwrap())
+            .to_vec();
+        Palette::new(colors, &csscolorparser::parse(stroke_color).unwrap())
+    }
+
+    fn hex(color: Option<csscolorparser::Color>) -> Option<String> {
+        color.map(|color| color.to_css_hex())
+    }
+
+    #[test]
+    fn cycles_from_the_stroke_color() {
+        let mut palette = palette("#00ff00");
+        assert_eq!(hex(palette.next()), Some("#0000ff".to_string()));
+        assert_eq!(hex(palette.next()), Some("#ff0000".to_string()));
+
+        // a color from outside the palette starts over at either end
+        palette.sync(&csscolorparser::parse("#ffffff").unwrap());
+        assert_eq!(hex(palette.previous()), Some("#0000ff".to_string()));
+        palette.sync(&csscolorparser::parse("#ffffff").unwrap());
+        assert_eq!(hex(palette.next()), Some("#ff0000".to_string()));
+
+        let mut empty = Palette::new(Vec::new(), &csscolorparser::parse("#ffffff").unwrap());
+        assert_eq!(empty.next(), None);
+        assert_eq!(empty.previous(), None);
+    }
+
+    #[test]
+    fn picks_colors_counting_from_one() {
+        let mut palette = palette("#ffffff");
+        assert_eq!(hex(palette.pick(1)), Some("#ff0000".to_string()));
+        assert_eq!(hex(palette.pick(3)), Some("#0000ff".to_string()));
+        assert_eq!(palette.pick(0), None);
+        assert_eq!(palette.pick(4), None);
+
+        // cycling goes on from the picked color, picking one that doesn't exist changes nothing
+        assert_eq!(hex(palette.next()), Some("#ff0000".to_string()));
+        palette.pick(2);
+        assert_eq!(hex(palette.previous()), Some("#ff0000".to_string()));
+
+        let mut empty = Palette::new(Vec::new(), &csscolorparser::parse("#ffffff").unwrap());
+        assert_eq!(empty.pick(1), None);
+    }
+}
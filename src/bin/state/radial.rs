@@ -0,0 +1,200 @@
+use std::f32::consts::TAU;
+
+use wayland_backend::client::ObjectId;
+
+use wayland_client::QueueHandle;
+
+use lyon::math::Point;
+use lyon::math::point;
+use lyon::math::vector;
+use lyon::path::Path;
+
+use chameleos::Command;
+use chameleos::Tool;
+
+use super::draw::DrawState;
+use super::input::Device;
+use super::input::ToolEvent;
+use super::ui::Action;
+use super::ui::Look;
+use super::ui::Painter;
+
+use crate::render::Geometry;
+
+/// Outer radius of the menu, in logical pixels
+const RADIUS: f32 = 120.0;
+/// Letting go this close to the center picks nothing
+const DEAD_ZONE: f32 = 32.0;
+/// Distance of the icons from the center
+const ICON_DISTANCE: f32 = 84.0;
+/// Line segments per slice for the highlighted arc
+const ARC_SEGMENTS: usize = 12;
+
+/// A pie menu around the spot where its button was pressed, a slice is picked by moving
+/// towards it and letting go of the button
+pub struct RadialMenu {
+    device: Device,
+    /// in surface coordinates, y going down
+    center: Point,
+    look: Look,
+    actions: Vec<Action>,
+    selected: Option<usize>,
+    geometry: Geometry,
+}
+
+impl RadialMenu {
+    pub fn new(device: Device, (x, y): (f64, f64), look: Look, draw: &DrawState) -> Self {
+        let actions = [Action::Tool(Tool::Pen), Action::Tool(Tool::Eraser)]
+            .into_iter()
+            .chain(super::ui::WIDTH_PRESETS.into_iter().map(Action::Width))
+            .chain((1..=look.colors.len()).map(Action::Color))
+            .collect();
+
+        let mut menu = Self {
+            device,
+            center: point(x as f32, y as f32),
+            look,
+            actions,
+            selected: None,
+            // needs the rest of the menu to be built
+            geometry: Geometry::new(lyon::tessellation::VertexBuffers::new()),
+        };
+        menu.geometry = menu.tessellate(draw);
+        menu
+    }
+
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+
+    /// Slices start at the top and go clockwise
+    fn slice_angle(&self) -> f32 {
+        TAU / self.actions.len() as f32
+    }
+
+    fn direction(&self, slice: usize) -> f32 {
+        -TAU / 4.0 + slice as f32 * self.slice_angle()
+    }
+
+    /// Selects the slice in the direction of a position, returns whether that changed anything
+    pub fn point_at(&mut self, (x, y): (f64, f64), draw: &DrawState) -> bool {
+        let offset = point(x as f32, y as f32) - self.center;
+        let selected = if offset.length() < DEAD_ZONE {
+            None
+        } else {
+            let angle = offset.y.atan2(offset.x) + TAU / 4.0;
+            let slice = (angle / self.slice_angle()).round() as isize;
+            Some(slice.rem_euclid(self.actions.len() as isize) as usize)
+        };
+
+        if selected == self.selected {
+            return false;
+        }
+
+        self.selected = selected;
+        self.geometry = self.tessellate(draw);
+        true
+    }
+
+    /// The commands of the selected slice
+    pub fn commands(&self) -> Vec<Command> {
+        self.selected
+            .map(|slice| self.actions[slice].commands())
+            .unwrap_or_default()
+    }
+
+    fn tessellate(&self, draw: &DrawState) -> Geometry {
+        let mut painter = Painter::new(draw, draw.size().1 as f32);
+        painter.fill(
+            &super::ui::circle(self.center, RADIUS),
+            &super::ui::BACKGROUND,
+        );
+
+        if let Some(slice) = self.selected {
+            painter.fill(&self.wedge(slice), &super::ui::HIGHLIGHT);
+        }
+
+        for (slice, action) in self.actions.iter().enumerate() {
+            let angle = self.direction(slice);
+            let center = self.center + vector(angle.cos(), angle.sin()) * ICON_DISTANCE;
+            if action.is_current(&self.look) {
+                painter.fill(&super::ui::circle(center, 17.0), &super::ui::HIGHLIGHT);
+            }
+            painter.icon(*action, center, &self.look);
+        }
+
+        painter.finish()
+    }
+
+    /// The ring segment of a slice, between the dead zone and the outer edge
+    fn wedge(&self, slice: usize) -> Path {
+        let half = self.slice_angle() / 2.0;
+        let start = self.direction(slice) - half;
+        let at = |radius: f32, i: usize| {
+            let angle = start + 2.0 * half * i as f32 / ARC_SEGMENTS as f32;
+            self.center + vector(angle.cos(), angle.sin()) * radius
+        };
+
+        let mut builder = Path::builder();
+        builder.begin(at(DEAD_ZONE, 0));
+        for i in 0..=ARC_SEGMENTS {
+            builder.line_to(at(RADIUS, i));
+        }
+        for i in (0..=ARC_SEGMENTS).rev() {
+            builder.line_to(at(DEAD_ZONE, i));
+        }
+        builder.end(true);
+        builder.build()
+    }
+}
+
+/// Opens, steers and closes the radial menu for a device, with the button pressed and released
+/// in one frame of its events
+///
+/// Returns whether the events were used up by the menu, the device shouldn't draw with them then.
+pub fn steer(
+    state: &mut super::State,
+    device: Device,
+    (pressed, released): (Option<u32>, Option<u32>),
+    surface: Option<&ObjectId>,
+    pos: Option<(f64, f64)>,
+    qhandle: &QueueHandle<super::State>,
+) -> bool {
+    let Some(button) = state.radial_button else {
+        return false;
+    };
+
+    if pressed == Some(button.0)
+        && let Some(surface) = surface
+        && let Some(pos) = pos
+        && state.overlays.is_active(surface)
+    {
+        // a line in progress ends where the menu opens
+        state
+            .input
+            .dispatch(&mut state.overlays, device, ToolEvent::Up);
+
+        let look = state.ui_look();
+        if let Some(overlay) = state.overlays.get_mut(surface) {
+            overlay.open_radial(device, pos, look);
+        }
+        return true;
+    }
+
+    let Some(overlay) = state.overlays.with_radial_mut(device) else {
+        return false;
+    };
+
+    if released == Some(button.0) {
+        for command in overlay.close_radial() {
+            state.execute(command, qhandle);
+        }
+    } else if let Some(pos) = pos {
+        overlay.point_radial(pos);
+    }
+    true
+}
@@ -0,0 +1,213 @@
+use wayland_backend::client::ObjectId;
+
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::Proxy;
+use wayland_client::QueueHandle;
+
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use wayland_protocols::xdg::shell::client::xdg_surface::XdgSurface;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::XdgToplevel;
+use wayland_protocols::xdg::shell::client::xdg_wm_base::XdgWmBase;
+
+use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::ZwlrLayerSurfaceV1;
+
+use log::Level;
+use log::log;
+
+use crate::render::Backend;
+
+use super::keyboard::KeyboardFocus;
+
+/// The role that puts an overlay on screen
+pub enum ShellSurface {
+    Layer(ZwlrLayerSurfaceV1),
+    /// a transparent fullscreen window, for compositors without layer shell
+    Toplevel {
+        xdg_surface: XdgSurface,
+        toplevel: XdgToplevel,
+        /// the size from the last toplevel configure, applied with the next surface configure
+        pending_size: (u32, u32),
+    },
+}
+
+impl ShellSurface {
+    /// Prefers a layer surface, the wayland state makes sure one of the shells exists
+    pub fn new(
+        wayland: &super::WaylandState,
+        surface: &WlSurface,
+        output: Option<&WlOutput>,
+        force_backend: Option<Backend>,
+        qhandle: &QueueHandle<super::State>,
+    ) -> Self {
+        use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1::Layer;
+        use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::Anchor;
+        use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::KeyboardInteractivity;
+
+        if let Some(ref layer_shell) = wayland.layer_shell {
+            let layer_surface = layer_shell.get_layer_surface(
+                surface,
+                output,
+                Layer::Overlay,
+                "chameleos".to_string(),
+                qhandle,
+                force_backend,
+            );
+
+            layer_surface.set_anchor(Anchor::all());
+            layer_surface.set_keyboard_interactivity(KeyboardInteractivity::None);
+            layer_surface.set_exclusive_zone(-1);
+
+            return Self::Layer(layer_surface);
+        }
+
+        let xdg_wm_base = wayland
+            .xdg_wm_base
+            .as_ref()
+            .expect("either layer shell or xdg shell is available");
+        let xdg_surface = xdg_wm_base.get_xdg_surface(surface, qhandle, ());
+        let toplevel = xdg_surface.get_toplevel(qhandle, ());
+
+        toplevel.set_title("chameleos".to_string());
+        toplevel.set_app_id("chameleos".to_string());
+        toplevel.set_fullscreen(output);
+
+        Self::Toplevel {
+            xdg_surface,
+            toplevel,
+            pending_size: (0, 0),
+        }
+    }
+
+    /// Whichever object the configure events arrive on
+    pub fn id(&self) -> ObjectId {
+        match self {
+            ShellSurface::Layer(layer_surface) => layer_surface.id(),
+            ShellSurface::Toplevel { xdg_surface, .. } => xdg_surface.id(),
+        }
+    }
+
+    /// Fullscreen windows get keyboard focus like any other window
+    pub fn set_keyboard_focus(&self, keyboard_focus: KeyboardFocus) {
+        use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::KeyboardInteractivity;
+
+        let ShellSurface::Layer(layer_surface) = self else {
+            return;
+        };
+
+        let interactivity = match keyboard_focus {
+            KeyboardFocus::None => KeyboardInteractivity::None,
+            // on demand only exists since version 4
+            KeyboardFocus::OnDemand if layer_surface.version() >= 4 => {
+                KeyboardInteractivity::OnDemand
+            }
+            KeyboardFocus::OnDemand | KeyboardFocus::Exclusive => KeyboardInteractivity::Exclusive,
+        };
+        layer_surface.set_keyboard_interactivity(interactivity);
+    }
+
+    pub fn destroy(&self) {
+        match self {
+            ShellSurface::Layer(layer_surface) => layer_surface.destroy(),
+            ShellSurface::Toplevel {
+                xdg_surface,
+                toplevel,
+                ..
+            } => {
+                toplevel.destroy();
+                xdg_surface.destroy();
+            }
+        }
+    }
+}
+
+impl Dispatch<XdgWmBase, (), super::State> for super::overlay::Overlays {
+    fn event(
+        _state: &mut super::State,
+        xdg_wm_base: &XdgWmBase,
+        event: <XdgWmBase as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "XdgWmBase: {:?}", event);
+
+        use wayland_protocols::xdg::shell::client::xdg_wm_base::Event;
+        if let Event::Ping { serial } = event {
+            xdg_wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, (), super::State> for super::overlay::Overlays {
+    fn event(
+        state: &mut super::State,
+        xdg_surface: &XdgSurface,
+        event: <XdgSurface as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "XdgSurface: {:?}", event);
+
+        use wayland_protocols::xdg::shell::client::xdg_surface::Event;
+        if let Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+
+            if let Some(overlay) = state.overlays.by_shell_surface_mut(&xdg_surface.id())
+                && let ShellSurface::Toplevel { pending_size, .. } = overlay.shell
+            {
+                overlay.configure(
+                    &state.wayland.display,
+                    pending_size.0,
+                    pending_size.1,
+                    state.force_backend,
+                    state.rescale_on_resize,
+                );
+            }
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, (), super::State> for super::overlay::Overlays {
+    fn event(
+        state: &mut super::State,
+        toplevel: &XdgToplevel,
+        event: <XdgToplevel as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Info, "XdgToplevel: {:?}", event);
+
+        let overlay = state.overlays.iter().find(|overlay| {
+            matches!(overlay.shell, ShellSurface::Toplevel { toplevel: ref other, .. } if other == toplevel)
+        });
+        let Some(surface) = overlay.map(|overlay| overlay.surface.id()) else {
+            return;
+        };
+
+        use wayland_protocols::xdg::shell::client::xdg_toplevel::Event;
+        match event {
+            // a size of 0 leaves it up to us, which a fullscreen window shouldn't get
+            Event::Configure {
+                width,
+                height,
+                states: _,
+            } => {
+                if let Some(overlay) = state.overlays.get_mut(&surface)
+                    && let ShellSurface::Toplevel {
+                        ref mut pending_size,
+                        ..
+                    } = overlay.shell
+                {
+                    *pending_size = (width.max(0) as u32, height.max(0) as u32);
+                }
+            }
+            Event::Close => state.close_overlay(&surface, qhandle),
+            _ => {}
+        }
+    }
+}
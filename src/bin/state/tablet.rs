@@ -8,11 +8,9 @@ use wayland_client::Proxy;
 use wayland_client::QueueHandle;
 use wayland_client::WEnum;
 
-use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
-use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::WpCursorShapeDeviceV1;
-
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2::ZwpTabletPadV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::ZwpTabletSeatV2;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::Type;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::ZwpTabletToolV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_v2::ZwpTabletV2;
 
@@ -23,29 +21,137 @@ use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::EVT_TOOL_ADD
 use log::Level;
 use log::log;
 
-use super::mouse;
+use chameleos::Button;
+use chameleos::Preset;
+use chameleos::StylusBinding;
+use chameleos::TabletToolBinding;
+use chameleos::TabletToolType;
+use chameleos::Tool;
+use chameleos::ToolSerial;
+
+use super::bindings::ButtonMap;
+use super::cursor::Cursor;
+use super::input::Device;
+use super::input::Sample;
+use super::input::ToolEvent;
 
 #[derive(Default)]
 pub struct TabletState {
     event_sequence: EventSequence,
 
     tablet_seat: Option<ZwpTabletSeatV2>,
-    tablet_cursor_shape_devices: HashMap<ObjectId, WpCursorShapeDeviceV1>,
+    tablet_cursors: HashMap<ObjectId, Cursor>,
+    tablet_tool_types: HashMap<ObjectId, TabletToolType>,
+    tablet_tool_serials: HashMap<ObjectId, ToolSerial>,
+
+    /// tool and stroke settings used by each physical tool type,
+    /// e.g. the eraser end of a pen erases
+    type_presets: HashMap<TabletToolType, Preset>,
+    /// settings remembered per physical tool, these take precedence over the type presets
+    serial_presets: HashMap<ToolSerial, Preset>,
+    /// the tool that was last in proximity, which stroke changes get remembered for
+    last_tool: Option<ObjectId>,
 
+    in_proximity: bool,
+    /// the overlay the tool is over
+    surface: Option<ObjectId>,
     pos: Option<(f64, f64)>,
+    pressure: Option<f64>,
+    tilt: Option<(f64, f64)>,
     pen_held: bool,
-    button_held: bool,
+    buttons_held: Vec<u32>,
 }
 
 impl TabletState {
+    pub fn new(tool_bindings: &[TabletToolBinding], stylus_bindings: &[StylusBinding]) -> Self {
+        let mut type_presets = HashMap::from([(
+            TabletToolType::Eraser,
+            Preset {
+                tool: Some(Tool::Eraser),
+                ..Default::default()
+            },
+        )]);
+        for binding in tool_bindings {
+            type_presets.insert(binding.tool_type, binding.preset.clone());
+        }
+
+        let serial_presets = stylus_bindings
+            .iter()
+            .map(|binding| (binding.serial, binding.preset.clone()))
+            .collect();
+
+        Self {
+            type_presets,
+            serial_presets,
+            ..Default::default()
+        }
+    }
+
+    fn last_serial(&self) -> Option<ToolSerial> {
+        self.last_tool
+            .as_ref()
+            .and_then(|tool| self.tablet_tool_serials.get(tool))
+            .copied()
+    }
+
+    /// Remembers a stroke change for the physical tool in proximity, changes made with the mouse
+    /// or from the command line while no tool is near don't stick to the last one
+    pub fn remember_stroke(&mut self, width: Option<f32>, color: Option<csscolorparser::Color>) {
+        if !self.in_proximity {
+            return;
+        }
+        if let Some(serial) = self.last_serial() {
+            let preset = self.serial_presets.entry(serial).or_default();
+            if width.is_some() {
+                preset.width = width;
+            }
+            if color.is_some() {
+                preset.color = color;
+            }
+        }
+    }
+
+    pub fn set_stylus_preset(&mut self, preset: Preset) {
+        match self.last_serial() {
+            Some(serial) => {
+                log!(target: "chameleos::general", Level::Info, "stylus {serial}: {preset}");
+                self.serial_presets.insert(serial, preset);
+            }
+            None => log!(
+                target: "chameleos::general",
+                Level::Warn,
+                "no stylus with a hardware serial was in proximity yet"
+            ),
+        }
+    }
+
     pub fn set_tablet_seat(&mut self, tablet_seat: ZwpTabletSeatV2) {
         self.tablet_seat = Some(tablet_seat);
     }
 
-    fn update_state(&mut self, sequence: EventSequence) {
+    pub fn in_proximity(&self) -> bool {
+        self.in_proximity
+    }
+
+    fn update_state(&mut self, sequence: &EventSequence) {
         if let Some(new_pos) = sequence.motion {
             self.pos = Some(new_pos);
         }
+        if sequence.pressure.is_some() {
+            self.pressure = sequence.pressure;
+        }
+        if sequence.tilt.is_some() {
+            self.tilt = sequence.tilt;
+        }
+
+        if sequence.enter_serial.is_some() {
+            self.in_proximity = true;
+            self.surface = sequence.enter_surface.clone();
+        }
+        if sequence.proximity_out {
+            self.in_proximity = false;
+            self.surface = None;
+        }
 
         if sequence.pen_pressed {
             self.pen_held = true;
@@ -54,13 +160,43 @@ impl TabletState {
             self.pen_held = false;
         }
 
-        if sequence.button_pressed {
-            self.button_held = true;
+        if let Some(button) = sequence.button_pressed {
+            self.buttons_held.push(button);
         }
-        if sequence.button_released {
-            self.button_held = false;
+        if let Some(button) = sequence.button_released {
+            self.buttons_held.retain(|&held| held != button);
         }
     }
+
+    fn preset(&self, tablet_tool: &ObjectId) -> Option<Preset> {
+        let serial_preset = self
+            .tablet_tool_serials
+            .get(tablet_tool)
+            .and_then(|serial| self.serial_presets.get(serial));
+        let type_preset = self
+            .tablet_tool_types
+            .get(tablet_tool)
+            .and_then(|tool_type| self.type_presets.get(tool_type));
+
+        match (serial_preset, type_preset) {
+            (Some(serial_preset), Some(type_preset)) => Some(serial_preset.or(type_preset)),
+            (serial_preset, type_preset) => serial_preset.or(type_preset).cloned(),
+        }
+    }
+
+    /// The most recently pressed mapped stylus button decides the tool and stroke, settings it
+    /// leaves out come from the preset of the tool, then from whatever the pen tip is mapped to.
+    fn current_preset(&self, bindings: &ButtonMap, preset: Option<&Preset>) -> Preset {
+        let held = self
+            .buttons_held
+            .iter()
+            .rev()
+            .find_map(|&button| bindings.preset(button));
+        [held, preset, bindings.preset(Button::TIP.0)]
+            .into_iter()
+            .flatten()
+            .fold(Preset::default(), |current, next| current.or(next))
+    }
 }
 
 impl Dispatch<ZwpTabletSeatV2, (), super::State> for TabletState {
@@ -78,15 +214,13 @@ impl Dispatch<ZwpTabletSeatV2, (), super::State> for TabletState {
         match event {
             Event::TabletAdded { id: _ } => {}
             Event::ToolAdded { id } => {
-                let cursor_shape_device =
+                if let Some(cursor) =
                     state
-                        .wayland
-                        .cursor_shape_manager
-                        .get_tablet_tool_v2(&id, qhandle, ());
-                state
-                    .tablet
-                    .tablet_cursor_shape_devices
-                    .insert(id.id(), cursor_shape_device);
+                        .cursors
+                        .for_tablet_tool(&id, &state.wayland.compositor, qhandle)
+                {
+                    state.tablet.tablet_cursors.insert(id.id(), cursor);
+                }
             }
             Event::PadAdded { id: _ } => {}
             _ => {}
@@ -107,56 +241,173 @@ impl Dispatch<ZwpTabletToolV2, (), super::State> for TabletState {
         event: <ZwpTabletToolV2 as Proxy>::Event,
         _data: &(),
         _conn: &Connection,
-        _qhandle: &QueueHandle<super::State>,
+        qhandle: &QueueHandle<super::State>,
     ) {
         log!(target: "chameleos::wayland", Level::Debug, "ZwpTabletToolV2: {:?}", event);
 
         let tablet = &mut state.tablet;
-        let draw = &mut state.draw;
-
-        // TODO this is very similar to MouseState
-        if let Some(sequence) = tablet.event_sequence.dispatch(event) {
-            tablet.update_state(sequence);
-
-            if let Some(device) = tablet.tablet_cursor_shape_devices.get(&tablet_tool.id())
-                && let Some(serial) = sequence.enter_serial
-            {
-                device.set_shape(serial, Shape::Crosshair);
-            }
-
-            let pen_pos = mouse::draw_pos(
-                sequence.pen_pressed,
-                sequence.motion,
-                tablet.pen_held,
-                tablet.pos,
-            );
-
-            if let Some(pos) = pen_pos {
-                if tablet.button_held {
-                    draw.erase(pos);
-                } else {
-                    draw.add_point_to_line(pos);
+
+        use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::Event;
+        match event {
+            Event::Type {
+                tool_type: WEnum::Value(tool_type),
+            } => {
+                if let Some(tool_type) = convert_tool_type(tool_type) {
+                    tablet.tablet_tool_types.insert(tablet_tool.id(), tool_type);
                 }
             }
-
-            if sequence.pen_released || sequence.button_pressed {
-                draw.cut_line();
+            Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                let serial =
+                    ToolSerial(((hardware_serial_hi as u64) << 32) | hardware_serial_lo as u64);
+                log!(target: "chameleos::general", Level::Info, "tablet tool with serial {serial}");
+                tablet.tablet_tool_serials.insert(tablet_tool.id(), serial);
+            }
+            Event::HardwareIdWacom {
+                hardware_id_hi,
+                hardware_id_lo,
+            } => {
+                let id = ((hardware_id_hi as u64) << 32) | hardware_id_lo as u64;
+                log!(target: "chameleos::general", Level::Info, "tablet tool with wacom id {id:#x}");
             }
+            Event::Removed => {
+                let id = tablet_tool.id();
+                tablet.tablet_tool_types.remove(&id);
+                tablet.tablet_tool_serials.remove(&id);
+                if let Some(cursor) = tablet.tablet_cursors.remove(&id) {
+                    cursor.destroy();
+                }
+                if tablet.last_tool.as_ref() == Some(&id) {
+                    tablet.last_tool = None;
+                }
+                tablet_tool.destroy();
+            }
+            event => {
+                if let Some(sequence) = tablet.event_sequence.dispatch(event) {
+                    handle_sequence(state, tablet_tool, sequence, qhandle);
+                }
+            }
+        }
+    }
+}
+
+fn handle_sequence(
+    state: &mut super::State,
+    tablet_tool: &ZwpTabletToolV2,
+    sequence: EventSequence,
+    qhandle: &QueueHandle<super::State>,
+) {
+    let tablet = &mut state.tablet;
+
+    let was_down = tablet.pen_held;
+    let previous_tool = tablet
+        .current_preset(&state.bindings, tablet.preset(&tablet_tool.id()).as_ref())
+        .tool;
+    tablet.update_state(&sequence);
+
+    let preset = tablet.current_preset(&state.bindings, tablet.preset(&tablet_tool.id()).as_ref());
+    let tool = preset.tool;
+    let stroke = state.stroke.with_preset(&preset);
+
+    if let Some(cursor) = tablet.tablet_cursors.get(&tablet_tool.id())
+        && let Some(serial) = sequence.enter_serial
+    {
+        cursor.set_for_tablet_tool(tablet_tool, serial);
+    }
+
+    if sequence.enter_serial.is_some() {
+        tablet.last_tool = Some(tablet_tool.id());
+    }
+
+    let buttons = (sequence.button_pressed, sequence.button_released);
+    let (surface, pos) = (tablet.surface.clone(), tablet.pos);
+    let in_menu = super::radial::steer(
+        state,
+        Device::Tablet,
+        buttons,
+        surface.as_ref(),
+        pos,
+        qhandle,
+    );
+
+    let tablet = &mut state.tablet;
+    let overlays = &mut state.overlays;
+    let input = &mut state.input;
+
+    if state.cursors.draws_brush() {
+        let hover = tablet.surface.as_ref().zip(tablet.pos);
+        overlays.move_brush(Device::Tablet, hover, tool, &stroke);
+    }
+
+    // switching tools while the pen is down starts a new line
+    let tool_changed = was_down && tool != previous_tool;
+    if sequence.pen_released || tool_changed {
+        input.dispatch(overlays, Device::Tablet, ToolEvent::Up);
+    }
+
+    let Some(pos) = tablet.pos else {
+        return;
+    };
+    let sample = Sample {
+        pos,
+        pressure: tablet.pressure,
+        tilt: tablet.tilt,
+    };
+
+    if in_menu {
+        return;
+    }
+
+    if tablet.pen_held
+        && (sequence.pen_pressed || tool_changed)
+        && let Some(tool) = tool
+        && let Some(ref surface) = tablet.surface
+    {
+        let event = ToolEvent::Down {
+            surface: surface.clone(),
+            tool,
+            stroke,
+            sample,
+        };
+        for command in input.dispatch(overlays, Device::Tablet, event) {
+            state.execute(command, qhandle);
         }
+    } else if sequence.motion.is_some() {
+        input.dispatch(overlays, Device::Tablet, ToolEvent::Move { sample });
+    }
+}
+
+fn convert_tool_type(tool_type: Type) -> Option<TabletToolType> {
+    match tool_type {
+        Type::Pen => Some(TabletToolType::Pen),
+        Type::Eraser => Some(TabletToolType::Eraser),
+        Type::Brush => Some(TabletToolType::Brush),
+        Type::Pencil => Some(TabletToolType::Pencil),
+        Type::Airbrush => Some(TabletToolType::Airbrush),
+        Type::Finger => Some(TabletToolType::Finger),
+        Type::Mouse => Some(TabletToolType::Mouse),
+        Type::Lens => Some(TabletToolType::Lens),
+        _ => None,
     }
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone)]
 struct EventSequence {
     motion: Option<(f64, f64)>,
+    pressure: Option<f64>,
+    tilt: Option<(f64, f64)>,
 
     pen_pressed: bool,
     pen_released: bool,
 
-    button_pressed: bool,
-    button_released: bool,
+    button_pressed: Option<u32>,
+    button_released: Option<u32>,
 
     enter_serial: Option<u32>,
+    enter_surface: Option<ObjectId>,
+    proximity_out: bool,
 }
 
 impl EventSequence {
@@ -166,9 +417,14 @@ impl EventSequence {
             Event::ProximityIn {
                 serial,
                 tablet: _,
-                surface: _,
+                surface,
             } => {
                 self.enter_serial = Some(serial);
+                self.enter_surface = Some(surface.id());
+                None
+            }
+            Event::ProximityOut => {
+                self.proximity_out = true;
                 None
             }
             Event::Down { serial: _ } => {
@@ -189,12 +445,10 @@ impl EventSequence {
                 state: button_state,
             } => {
                 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::ButtonState;
-                if button == 331 {
-                    match button_state {
-                        WEnum::Value(ButtonState::Released) => self.button_released = true,
-                        WEnum::Value(ButtonState::Pressed) => self.button_pressed = true,
-                        _ => {}
-                    }
+                match button_state {
+                    WEnum::Value(ButtonState::Released) => self.button_released = Some(button),
+                    WEnum::Value(ButtonState::Pressed) => self.button_pressed = Some(button),
+                    _ => {}
                 }
                 None
             }
@@ -203,8 +457,12 @@ impl EventSequence {
                 std::mem::swap(self, &mut tmp);
                 Some(tmp)
             }
-            Event::Pressure { pressure: _ } => {
-                // TODO maybe support pressure sensitivity down the line
+            Event::Pressure { pressure } => {
+                self.pressure = Some(pressure as f64 / 65535.0);
+                None
+            }
+            Event::Tilt { tilt_x, tilt_y } => {
+                self.tilt = Some((tilt_x, tilt_y));
                 None
             }
             _ => None,
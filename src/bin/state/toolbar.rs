@@ -0,0 +1,155 @@
+use lyon::math::Box2D;
+use lyon::math::point;
+
+use chameleos::Command;
+use chameleos::Edge;
+use chameleos::Tool;
+
+use super::draw::DrawState;
+use super::ui::Action;
+use super::ui::Look;
+use super::ui::Painter;
+
+use crate::render::Geometry;
+
+/// Size of a button, in logical pixels
+const BUTTON_SIZE: f32 = 36.0;
+/// Space between buttons of a group
+const BUTTON_GAP: f32 = 4.0;
+/// Space between groups of buttons
+const GROUP_GAP: f32 = 14.0;
+/// Space between the buttons and the border of the toolbar
+const PADDING: f32 = 6.0;
+/// Space between the toolbar and the screen edge it is docked to
+const MARGIN: f32 = 12.0;
+
+struct Item {
+    action: Action,
+    /// in surface coordinates, y going down
+    rect: Box2D,
+}
+
+/// Buttons for the most common commands, drawn on top of the lines while input is active
+pub struct Toolbar {
+    edge: Edge,
+    look: Look,
+    rect: Box2D,
+    items: Vec<Item>,
+    geometry: Geometry,
+}
+
+impl Toolbar {
+    pub fn new(edge: Edge, look: Look, (width, height): (u32, u32), draw: &DrawState) -> Self {
+        let groups = [
+            [
+                Tool::Pen,
+                Tool::Eraser,
+                Tool::Laser,
+                Tool::Line,
+                Tool::Rectangle,
+                Tool::Ellipse,
+            ]
+            .into_iter()
+            .map(Action::Tool)
+            .collect(),
+            super::ui::WIDTH_PRESETS
+                .into_iter()
+                .map(Action::Width)
+                .collect(),
+            (1..=look.colors.len()).map(Action::Color).collect(),
+            vec![Action::Undo, Action::Redo, Action::Clear],
+        ];
+        let groups: Vec<Vec<Action>> = groups.into_iter().filter(|g| !g.is_empty()).collect();
+
+        let buttons: usize = groups.iter().map(Vec::len).sum();
+        let gaps = buttons.saturating_sub(groups.len()) as f32 * BUTTON_GAP
+            + groups.len().saturating_sub(1) as f32 * GROUP_GAP;
+        let length = buttons as f32 * BUTTON_SIZE + gaps + 2.0 * PADDING;
+        let thickness = BUTTON_SIZE + 2.0 * PADDING;
+
+        let (width, height) = (width as f32, height as f32);
+        let horizontal = matches!(edge, Edge::Top | Edge::Bottom);
+        let origin = match edge {
+            Edge::Top => point((width - length) / 2.0, MARGIN),
+            Edge::Bottom => point((width - length) / 2.0, height - MARGIN - thickness),
+            Edge::Left => point(MARGIN, (height - length) / 2.0),
+            Edge::Right => point(width - MARGIN - thickness, (height - length) / 2.0),
+        };
+        let size = if horizontal {
+            lyon::math::size(length, thickness)
+        } else {
+            lyon::math::size(thickness, length)
+        };
+        let rect = Box2D::from_origin_and_size(origin, size);
+
+        let mut items = Vec::with_capacity(buttons);
+        let mut offset = PADDING;
+        for group in groups {
+            for action in group {
+                let start = if horizontal {
+                    origin + lyon::math::vector(offset, PADDING)
+                } else {
+                    origin + lyon::math::vector(PADDING, offset)
+                };
+                items.push(Item {
+                    action,
+                    rect: Box2D::from_origin_and_size(
+                        start,
+                        lyon::math::size(BUTTON_SIZE, BUTTON_SIZE),
+                    ),
+                });
+                offset += BUTTON_SIZE + BUTTON_GAP;
+            }
+            offset += GROUP_GAP - BUTTON_GAP;
+        }
+
+        let mut painter = Painter::new(draw, height);
+        painter.fill(
+            &super::ui::rounded_rectangle(&rect, PADDING * 2.0),
+            &super::ui::BACKGROUND,
+        );
+        for item in &items {
+            if item.action.is_current(&look) {
+                painter.fill(
+                    &super::ui::rounded_rectangle(&item.rect, PADDING),
+                    &super::ui::HIGHLIGHT,
+                );
+            }
+            painter.icon(item.action, item.rect.center(), &look);
+        }
+
+        Self {
+            edge,
+            look,
+            rect,
+            items,
+            geometry: painter.finish(),
+        }
+    }
+
+    pub fn is_built_for(&self, edge: Edge, look: &Look) -> bool {
+        self.edge == edge && self.look == *look
+    }
+
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+
+    /// The commands of the button at a position, `None` if the position isn't on the toolbar
+    ///
+    /// The space between the buttons belongs to the toolbar as well, so nothing gets drawn there.
+    pub fn hit(&self, (x, y): (f64, f64)) -> Option<Vec<Command>> {
+        let pos = point(x as f32, y as f32);
+        if !self.rect.contains(pos) {
+            return None;
+        }
+
+        Some(
+            self.items
+                .iter()
+                .find(|item| item.rect.contains(pos))
+                .map(|item| item.action.commands())
+                .unwrap_or_default(),
+        )
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use wayland_backend::client::ObjectId;
+
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::Proxy;
+use wayland_client::QueueHandle;
+
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::protocol::wl_touch::WlTouch;
+
+use log::Level;
+use log::log;
+
+use chameleos::Button;
+
+use super::input::Device;
+use super::input::Sample;
+use super::input::ToolEvent;
+
+/// Both fingers of a two finger tap need to go down and up within this many milliseconds
+const TAP_TIME: u32 = 300;
+/// Fingers may move this far (in surface coordinates) and still count as a tap
+const TAP_DISTANCE: f64 = 20.0;
+
+struct TouchPoint {
+    /// the overlay the finger went down on
+    surface: ObjectId,
+    down_time: u32,
+    start_pos: (f64, f64),
+    pos: (f64, f64),
+}
+
+/// A possible two finger tap, which undoes the last line
+struct Tap {
+    fingers: [i32; 2],
+    start_time: u32,
+    valid: bool,
+    released: usize,
+}
+
+#[derive(Default)]
+pub struct TouchState {
+    event_sequence: EventSequence,
+
+    /// the seat's touchscreen, if it has one
+    wl_touch: Option<WlTouch>,
+
+    touches: HashMap<i32, TouchPoint>,
+    /// touches with a contact larger than this are considered palms
+    palm_rejection: Option<f64>,
+    tap: Option<Tap>,
+}
+
+impl TouchState {
+    pub fn new(palm_rejection: Option<f64>) -> Self {
+        Self {
+            palm_rejection,
+            ..Default::default()
+        }
+    }
+
+    fn is_palm(&self, major: f64) -> bool {
+        self.palm_rejection.is_some_and(|max| major > max)
+    }
+
+    fn check_tap(&mut self, time: u32) {
+        let touches = &self.touches;
+        if let Some(ref mut tap) = self.tap
+            && tap.valid
+        {
+            let moved = tap.fingers.iter().any(|id| {
+                touches.get(id).is_some_and(|touch| {
+                    let (dx, dy) = (
+                        touch.pos.0 - touch.start_pos.0,
+                        touch.pos.1 - touch.start_pos.1,
+                    );
+                    dx * dx + dy * dy > TAP_DISTANCE * TAP_DISTANCE
+                })
+            });
+
+            if moved || time.wrapping_sub(tap.start_time) > TAP_TIME {
+                tap.valid = false;
+            }
+        }
+    }
+}
+
+/// Gets the seat's touchscreen when it shows up and lets go of it once it's gone, seats announce
+/// their capabilities again whenever any device comes or goes
+pub fn update_capability(
+    state: &mut super::State,
+    seat: &WlSeat,
+    has_touch: bool,
+    qhandle: &QueueHandle<super::State>,
+) {
+    let touch = &mut state.touch;
+    match (has_touch, touch.wl_touch.take()) {
+        (true, None) => touch.wl_touch = Some(seat.get_touch(qhandle, ())),
+        (false, Some(wl_touch)) => {
+            // releasing was added in version 3
+            if wl_touch.version() >= 3 {
+                wl_touch.release();
+            }
+            // fingers that are still down won't be lifted anymore
+            for &id in touch.touches.keys() {
+                let overlays = &mut state.overlays;
+                state
+                    .input
+                    .dispatch(overlays, Device::Touch(id), ToolEvent::Cancel);
+            }
+            touch.touches.clear();
+            touch.tap = None;
+        }
+        (_, wl_touch) => touch.wl_touch = wl_touch,
+    }
+}
+
+impl Dispatch<WlTouch, (), super::State> for TouchState {
+    fn event(
+        state: &mut super::State,
+        _touch: &WlTouch,
+        event: <WlTouch as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        qhandle: &QueueHandle<super::State>,
+    ) {
+        log!(target: "chameleos::wayland", Level::Debug, "WlTouch: {:?}", event);
+
+        let touch = &mut state.touch;
+        let overlays = &mut state.overlays;
+        let input = &mut state.input;
+
+        let Some(sequence) = touch.event_sequence.dispatch(event) else {
+            return;
+        };
+
+        if sequence.cancel {
+            for &id in touch.touches.keys() {
+                input.dispatch(overlays, Device::Touch(id), ToolEvent::Cancel);
+            }
+            touch.touches.clear();
+            touch.tap = None;
+            return;
+        }
+
+        // while a pen is close to the tablet, anything touching the screen is probably a palm
+        let pen_nearby = touch.palm_rejection.is_some() && state.tablet.in_proximity();
+        let tool = state.bindings.get(Button::TIP.0);
+        let stroke = state.bindings.stroke(Button::TIP.0, &state.stroke);
+        // from fingers going down on the toolbar
+        let mut commands = Vec::new();
+
+        for (id, surface, pos, time) in sequence.down {
+            touch.touches.insert(
+                id,
+                TouchPoint {
+                    surface: surface.clone(),
+                    down_time: time,
+                    start_pos: pos,
+                    pos,
+                },
+            );
+
+            // a second finger going down right after the first might be a tap
+            match touch.tap {
+                None if touch.touches.len() == 2 => {
+                    let (&other, other_point) = touch
+                        .touches
+                        .iter()
+                        .find(|&(&other, _)| other != id)
+                        .unwrap();
+                    if time.wrapping_sub(other_point.down_time) <= TAP_TIME {
+                        touch.tap = Some(Tap {
+                            fingers: [other, id],
+                            start_time: other_point.down_time,
+                            valid: true,
+                            released: 0,
+                        });
+                    }
+                }
+                Some(ref mut tap) => tap.valid = false,
+                None => {}
+            }
+
+            if !pen_nearby && let Some(tool) = tool {
+                let event = ToolEvent::Down {
+                    surface,
+                    tool,
+                    stroke: stroke.clone(),
+                    sample: Sample::at(pos),
+                };
+                commands.extend(input.dispatch(overlays, Device::Touch(id), event));
+            }
+        }
+
+        for &(id, major) in &sequence.shape {
+            if touch.is_palm(major) {
+                input.dispatch(overlays, Device::Touch(id), ToolEvent::Cancel);
+            }
+        }
+
+        for &(id, pos) in &sequence.motion {
+            if let Some(point) = touch.touches.get_mut(&id) {
+                point.pos = pos;
+            }
+
+            let event = ToolEvent::Move {
+                sample: Sample::at(pos),
+            };
+            input.dispatch(overlays, Device::Touch(id), event);
+        }
+
+        if let Some(time) = sequence.time {
+            touch.check_tap(time);
+        }
+
+        for &id in &sequence.up {
+            let surface = touch.touches.remove(&id).map(|point| point.surface);
+
+            let in_tap = touch
+                .tap
+                .as_ref()
+                .is_some_and(|tap| tap.fingers.contains(&id));
+
+            if in_tap && let Some(ref mut tap) = touch.tap {
+                tap.released += 1;
+                // the dots of a tap shouldn't stay behind
+                let event = if tap.valid {
+                    ToolEvent::Cancel
+                } else {
+                    ToolEvent::Up
+                };
+                input.dispatch(overlays, Device::Touch(id), event);
+
+                if tap.released == 2 {
+                    if tap.valid
+                        && let Some(draw) = surface.and_then(|surface| overlays.draw_mut(&surface))
+                    {
+                        log!(target: "chameleos::general", Level::Info, "two finger tap");
+                        draw.undo();
+                    }
+                    touch.tap = None;
+                }
+            } else {
+                input.dispatch(overlays, Device::Touch(id), ToolEvent::Up);
+            }
+        }
+
+        for command in commands {
+            state.execute(command, qhandle);
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+struct EventSequence {
+    down: Vec<(i32, ObjectId, (f64, f64), u32)>,
+    up: Vec<i32>,
+    motion: Vec<(i32, (f64, f64))>,
+    shape: Vec<(i32, f64)>,
+    time: Option<u32>,
+    cancel: bool,
+}
+
+impl EventSequence {
+    fn dispatch(&mut self, event: <WlTouch as Proxy>::Event) -> Option<Self> {
+        use wayland_client::protocol::wl_touch::Event;
+        match event {
+            Event::Down {
+                serial: _,
+                time,
+                surface,
+                id,
+                x,
+                y,
+            } => {
+                self.down.push((id, surface.id(), (x, y), time));
+                self.time = Some(time);
+                None
+            }
+            Event::Up {
+                serial: _,
+                time,
+                id,
+            } => {
+                self.up.push(id);
+                self.time = Some(time);
+                None
+            }
+            Event::Motion { time, id, x, y } => {
+                self.motion.push((id, (x, y)));
+                self.time = Some(time);
+                None
+            }
+            Event::Shape {
+                id,
+                major,
+                minor: _,
+            } => {
+                self.shape.push((id, major));
+                None
+            }
+            Event::Cancel => {
+                self.cancel = true;
+                Some(std::mem::take(self))
+            }
+            Event::Frame => Some(std::mem::take(self)),
+            _ => None,
+        }
+    }
+}
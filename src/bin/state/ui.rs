@@ -0,0 +1,239 @@
+use lyon::math::Box2D;
+use lyon::math::Point;
+use lyon::math::point;
+use lyon::path::Path;
+use lyon::tessellation::VertexBuffers;
+
+use chameleos::Button;
+use chameleos::Command;
+use chameleos::Preset;
+use chameleos::Tool;
+
+use super::draw::DrawState;
+use super::draw::Stroke;
+
+use crate::render::Geometry;
+use crate::render::Vertex;
+
+/// Stroke widths to pick from
+pub const WIDTH_PRESETS: [f32; 4] = [4.0, 8.0, 16.0, 32.0];
+
+pub const BACKGROUND: csscolorparser::Color = csscolorparser::Color::new(0.12, 0.12, 0.12, 0.85);
+pub const HIGHLIGHT: csscolorparser::Color = csscolorparser::Color::new(1.0, 1.0, 1.0, 0.25);
+const ICON: csscolorparser::Color = csscolorparser::Color::new(1.0, 1.0, 1.0, 1.0);
+const ICON_WIDTH: f32 = 2.5;
+
+/// Everything the toolbar and the radial menu show, they only need to be built again when this
+/// changes
+#[derive(Clone, PartialEq)]
+pub struct Look {
+    pub stroke: Stroke,
+    /// whatever the left button and the pen tip draw with
+    pub tool: Option<Tool>,
+    pub colors: Vec<csscolorparser::Color>,
+}
+
+/// What a button of the toolbar or a slice of the radial menu does
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Tool(Tool),
+    Width(f32),
+    /// counting from 1, like `chamel color`
+    Color(usize),
+    Undo,
+    Redo,
+    Clear,
+}
+
+impl Action {
+    /// Picking a tool binds it to both the left mouse button and the pen tip, replacing whatever
+    /// `--bind` set up for them
+    pub fn commands(&self) -> Vec<Command> {
+        match *self {
+            Action::Tool(tool) => [Button::LEFT, Button::TIP]
+                .into_iter()
+                .map(|button| Command::Bind {
+                    button,
+                    preset: Preset {
+                        tool: Some(tool),
+                        ..Default::default()
+                    },
+                })
+                .collect(),
+            Action::Width(width) => vec![Command::StrokeWidth { width }],
+            Action::Color(index) => vec![Command::Color { index }],
+            Action::Undo => vec![Command::Undo],
+            Action::Redo => vec![Command::Redo],
+            Action::Clear => vec![Command::Clear],
+        }
+    }
+
+    /// Whether this is the tool, width or color in use
+    pub fn is_current(&self, look: &Look) -> bool {
+        match *self {
+            Action::Tool(tool) => look.tool == Some(tool),
+            Action::Width(width) => look.stroke.width == width,
+            Action::Color(index) => look.colors.get(index - 1) == Some(&look.stroke.color),
+            Action::Undo | Action::Redo | Action::Clear => false,
+        }
+    }
+}
+
+pub fn rounded_rectangle(rect: &Box2D, radius: f32) -> Path {
+    use lyon::path::Winding;
+    use lyon::path::builder::BorderRadii;
+
+    let mut builder = Path::builder();
+    builder.add_rounded_rectangle(rect, &BorderRadii::new(radius), Winding::Positive);
+    builder.build()
+}
+
+pub fn circle(center: Point, radius: f32) -> Path {
+    use lyon::path::Winding;
+
+    let mut builder = Path::builder();
+    builder.add_circle(center, radius, Winding::Positive);
+    builder.build()
+}
+
+/// Tessellates shapes laid out in surface coordinates, with y going down like the pointer
+pub struct Painter<'a> {
+    draw: &'a DrawState,
+    height: f32,
+    geometry: VertexBuffers<Vertex, u16>,
+    /// stroked in one go at the end, on top of everything else
+    icons: lyon::path::path::Builder,
+}
+
+impl<'a> Painter<'a> {
+    pub fn new(draw: &'a DrawState, height: f32) -> Self {
+        Self {
+            draw,
+            height,
+            geometry: VertexBuffers::new(),
+            icons: Path::builder(),
+        }
+    }
+
+    /// lines go up from the bottom, unlike the pointer
+    fn flip(height: f32, p: Point) -> Point {
+        point(p.x, height - p.y)
+    }
+
+    pub fn fill(&mut self, path: &Path, color: &csscolorparser::Color) {
+        use lyon::tessellation::BuffersBuilder;
+        use lyon::tessellation::FillOptions;
+        use lyon::tessellation::FillTessellator;
+        use lyon::tessellation::FillVertex;
+
+        let color = self.draw.vertex_color(color);
+        let height = self.height;
+        FillTessellator::new()
+            .tessellate_path(
+                path,
+                &FillOptions::default(),
+                &mut BuffersBuilder::new(&mut self.geometry, |vertex: FillVertex| {
+                    Vertex::new(Self::flip(height, vertex.position()), &color)
+                }),
+            )
+            .unwrap();
+    }
+
+    fn polyline(&mut self, points: &[Point]) {
+        self.icons.begin(points[0]);
+        for &p in &points[1..] {
+            self.icons.line_to(p);
+        }
+        self.icons.end(false);
+    }
+
+    pub fn icon(&mut self, action: Action, center: Point, look: &Look) {
+        let at = |x: f32, y: f32| center + lyon::math::vector(x, y);
+        match action {
+            Action::Tool(Tool::Pen) => {
+                self.polyline(&[at(-9.0, 9.0), at(9.0, -9.0)]);
+                self.polyline(&[at(-9.0, 9.0), at(-9.0, 4.0)]);
+                self.polyline(&[at(-9.0, 9.0), at(-4.0, 9.0)]);
+            }
+            Action::Tool(Tool::Eraser) => {
+                self.polyline(&[
+                    at(-10.0, -5.0),
+                    at(10.0, -5.0),
+                    at(10.0, 5.0),
+                    at(-10.0, 5.0),
+                    at(-10.0, -5.0),
+                ]);
+                self.polyline(&[at(-2.0, -5.0), at(-2.0, 5.0)]);
+            }
+            Action::Tool(Tool::Laser) => {
+                self.fill(&circle(center, 3.5), &ICON);
+                for (x, y) in [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)] {
+                    self.polyline(&[at(x * 7.0, y * 7.0), at(x * 11.0, y * 11.0)]);
+                }
+            }
+            Action::Tool(Tool::Line) => {
+                self.polyline(&[at(-9.0, 6.0), at(9.0, -6.0)]);
+                self.fill(&circle(at(-9.0, 6.0), 2.5), &ICON);
+                self.fill(&circle(at(9.0, -6.0), 2.5), &ICON);
+            }
+            Action::Tool(Tool::Rectangle) => {
+                self.polyline(&[
+                    at(-10.0, -8.0),
+                    at(10.0, -8.0),
+                    at(10.0, 8.0),
+                    at(-10.0, 8.0),
+                    at(-10.0, -8.0),
+                ]);
+            }
+            Action::Tool(Tool::Ellipse) => {
+                self.icons.add_ellipse(
+                    center,
+                    lyon::math::vector(11.0, 8.0),
+                    lyon::math::Angle::zero(),
+                    lyon::path::Winding::Positive,
+                );
+            }
+            Action::Width(width) => self.fill(&circle(center, (width / 2.0).min(14.0)), &ICON),
+            Action::Color(index) => {
+                self.fill(&circle(center, 12.0), &ICON);
+                self.fill(&circle(center, 10.5), &look.colors[index - 1]);
+            }
+            Action::Undo => {
+                self.polyline(&[at(9.0, 0.0), at(-9.0, 0.0)]);
+                self.polyline(&[at(-4.0, -5.0), at(-9.0, 0.0), at(-4.0, 5.0)]);
+            }
+            Action::Redo => {
+                self.polyline(&[at(-9.0, 0.0), at(9.0, 0.0)]);
+                self.polyline(&[at(4.0, -5.0), at(9.0, 0.0), at(4.0, 5.0)]);
+            }
+            Action::Clear => {
+                self.polyline(&[at(-8.0, -8.0), at(8.0, 8.0)]);
+                self.polyline(&[at(-8.0, 8.0), at(8.0, -8.0)]);
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Geometry {
+        use lyon::tessellation::BuffersBuilder;
+        use lyon::tessellation::StrokeOptions;
+        use lyon::tessellation::StrokeTessellator;
+        use lyon::tessellation::StrokeVertex;
+
+        let color = self.draw.vertex_color(&ICON);
+        let height = self.height;
+        StrokeTessellator::new()
+            .tessellate_path(
+                &self.icons.build(),
+                &StrokeOptions::default()
+                    .with_line_width(ICON_WIDTH)
+                    .with_line_cap(lyon::path::LineCap::Round)
+                    .with_line_join(lyon::path::LineJoin::Round),
+                &mut BuffersBuilder::new(&mut self.geometry, |vertex: StrokeVertex| {
+                    Vertex::new(Self::flip(height, vertex.position()), &color)
+                }),
+            )
+            .unwrap();
+
+        Geometry::new(self.geometry)
+    }
+}
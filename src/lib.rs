@@ -1,13 +1,438 @@
 use clap::Subcommand;
+use clap::ValueEnum;
 
-#[derive(Subcommand)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum Tool {
+    Pen,
+    Eraser,
+    /// Draws like the pen, but the line fades away right after letting go
+    Laser,
+    /// A straight line from where the tool went down to where it is
+    Line,
+    /// A rectangle spanned from where the tool went down to where it is
+    Rectangle,
+    /// An ellipse inside the rectangle spanned from where the tool went down to where it is
+    Ellipse,
+}
+
+impl Tool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Pen => "pen",
+            Tool::Eraser => "eraser",
+            Tool::Laser => "laser",
+            Tool::Line => "line",
+            Tool::Rectangle => "rectangle",
+            Tool::Ellipse => "ellipse",
+        }
+    }
+
+    /// Whether only the start and end point decide what gets drawn
+    pub fn is_shape(&self) -> bool {
+        matches!(self, Tool::Line | Tool::Rectangle | Tool::Ellipse)
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::from_str(s, true).ok()
+    }
+}
+
+/// Physical tool types as reported by the tablet protocol
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum TabletToolType {
+    Pen,
+    Eraser,
+    Brush,
+    Pencil,
+    Airbrush,
+    Finger,
+    Mouse,
+    Lens,
+}
+
+/// A tool with stroke settings, written as `[TOOL][:WIDTH[:COLOR]]`
+///
+/// Settings that are left out fall back to the current tool, stroke width and color,
+/// e.g. `pen::blue` only overrides the tool and color.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Preset {
+    pub tool: Option<Tool>,
+    pub width: Option<f32>,
+    pub color: Option<csscolorparser::Color>,
+}
+
+impl Preset {
+    /// Takes every setting this preset leaves out from `other`
+    pub fn or(&self, other: &Preset) -> Self {
+        Self {
+            tool: self.tool.or(other.tool),
+            width: self.width.or(other.width),
+            color: self.color.clone().or_else(|| other.color.clone()),
+        }
+    }
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.splitn(3, ':');
+
+        let tool = match split.next() {
+            None | Some("") => None,
+            Some(tool) => {
+                Some(Tool::parse(tool).ok_or_else(|| format!("unknown tool \"{tool}\""))?)
+            }
+        };
+
+        let width = match split.next() {
+            None | Some("") => None,
+            Some(width) => Some(
+                width
+                    .parse::<f32>()
+                    .map_err(|_| format!("couldn't parse width \"{width}\""))?,
+            ),
+        };
+
+        let color = match split.next() {
+            None | Some("") => None,
+            Some(color) => Some(
+                csscolorparser::parse(color)
+                    .map_err(|_| format!("couldn't parse color \"{color}\""))?,
+            ),
+        };
+
+        Ok(Self { tool, width, color })
+    }
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(tool) = self.tool {
+            write!(f, "{}", tool.name())?;
+        }
+        if self.width.is_some() || self.color.is_some() {
+            write!(f, ":")?;
+        }
+        if let Some(width) = self.width {
+            write!(f, "{width}")?;
+        }
+        if let Some(ref color) = self.color {
+            write!(f, ":{}", color.to_css_hex())?;
+        }
+        Ok(())
+    }
+}
+
+/// A pointer or stylus button, identified by its evdev code
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Button(pub u32);
+
+impl Button {
+    pub const LEFT: Self = Self(0x110);
+    pub const RIGHT: Self = Self(0x111);
+    pub const MIDDLE: Self = Self(0x112);
+    pub const SIDE: Self = Self(0x113);
+    pub const EXTRA: Self = Self(0x114);
+
+    /// The pen tip touching the tablet surface
+    pub const TIP: Self = Self(0x14a);
+    pub const STYLUS: Self = Self(0x14b);
+    pub const STYLUS2: Self = Self(0x14c);
+    pub const STYLUS3: Self = Self(0x149);
+
+    const NAMES: [(&'static str, Self); 9] = [
+        ("left", Self::LEFT),
+        ("right", Self::RIGHT),
+        ("middle", Self::MIDDLE),
+        ("side", Self::SIDE),
+        ("extra", Self::EXTRA),
+        ("tip", Self::TIP),
+        ("stylus", Self::STYLUS),
+        ("stylus2", Self::STYLUS2),
+        ("stylus3", Self::STYLUS3),
+    ];
+}
+
+impl std::str::FromStr for Button {
+    type Err = String;
+
+    /// Takes either a button name (e.g. `middle`, `stylus2`) or a raw evdev code
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|&(_, button)| button)
+            .or_else(|| s.parse::<u32>().ok().map(Self))
+            .ok_or_else(|| format!("unknown button \"{s}\""))
+    }
+}
+
+impl std::fmt::Display for Button {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match Self::NAMES.iter().find(|&&(_, button)| button == *self) {
+            Some((name, _)) => write!(f, "{name}"),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// A `BUTTON=PRESET` pair as given on the command line, e.g. `middle=eraser` or `side=pen:8:red`
+#[derive(Clone, Debug)]
+pub struct Binding {
+    pub button: Button,
+    pub preset: Preset,
+}
+
+impl std::str::FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (button, preset) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected BUTTON=PRESET, got \"{s}\""))?;
+        Ok(Self {
+            button: button.parse()?,
+            preset: preset.parse()?,
+        })
+    }
+}
+
+/// The hardware serial number of a physical tablet tool
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ToolSerial(pub u64);
+
+impl std::str::FromStr for ToolSerial {
+    type Err = String;
+
+    /// Takes a hexadecimal serial prefixed with `0x` or a decimal one
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => s.parse::<u64>(),
+        }
+        .map(Self)
+        .map_err(|_| format!("couldn't parse serial \"{s}\""))
+    }
+}
+
+impl std::fmt::Display for ToolSerial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+/// A `SERIAL=PRESET` pair as given on the command line
+#[derive(Clone, Debug)]
+pub struct StylusBinding {
+    pub serial: ToolSerial,
+    pub preset: Preset,
+}
+
+impl std::str::FromStr for StylusBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (serial, preset) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected SERIAL=PRESET, got \"{s}\""))?;
+        Ok(Self {
+            serial: serial.parse()?,
+            preset: preset.parse()?,
+        })
+    }
+}
+
+/// A `TYPE=PRESET` pair as given on the command line
+#[derive(Clone, Debug)]
+pub struct TabletToolBinding {
+    pub tool_type: TabletToolType,
+    pub preset: Preset,
+}
+
+impl std::str::FromStr for TabletToolBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tool_type, preset) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected TYPE=PRESET, got \"{s}\""))?;
+        Ok(Self {
+            tool_type: TabletToolType::from_str(tool_type, true)
+                .map_err(|_| format!("unknown tablet tool type \"{tool_type}\""))?,
+            preset: preset.parse()?,
+        })
+    }
+}
+
+/// A `BUTTON=COMMAND` pair as given on the command line
+///
+/// Commands are written the way they are sent over the socket, e.g. `undo` or `stroke_width 16`.
+#[derive(Clone)]
+pub struct PadButtonBinding {
+    pub button: u32,
+    pub command: Command,
+}
+
+impl std::str::FromStr for PadButtonBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (button, command) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected BUTTON=COMMAND, got \"{s}\""))?;
+        Ok(Self {
+            button: button
+                .trim()
+                .parse()
+                .map_err(|_| format!("couldn't parse pad button \"{button}\""))?,
+            command: Command::deserialize(command.trim().as_bytes())
+                .map_err(|e| format!("{e}: \"{command}\""))?,
+        })
+    }
+}
+
+/// Touchpad gestures that can run a command
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum GestureKind {
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+    PinchIn,
+    PinchOut,
+    Hold,
+}
+
+/// A touchpad gesture, written as `KIND[:FINGERS]`
+///
+/// Without a finger count the gesture matches any number of fingers.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Gesture {
+    pub kind: GestureKind,
+    pub fingers: Option<u32>,
+}
+
+impl std::str::FromStr for Gesture {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, fingers) = match s.trim().split_once(':') {
+            Some((kind, fingers)) => (kind, Some(fingers)),
+            None => (s.trim(), None),
+        };
+        Ok(Self {
+            kind: GestureKind::from_str(kind, true)
+                .map_err(|_| format!("unknown gesture \"{kind}\""))?,
+            fingers: fingers
+                .map(|fingers| {
+                    fingers
+                        .parse()
+                        .map_err(|_| format!("couldn't parse finger count \"{fingers}\""))
+                })
+                .transpose()?,
+        })
+    }
+}
+
+/// A `GESTURE=COMMAND` pair as given on the command line
+///
+/// Commands are written the way they are sent over the socket, e.g. `undo` or `stroke_width 16`.
+#[derive(Clone)]
+pub struct GestureBinding {
+    pub gesture: Gesture,
+    pub command: Command,
+}
+
+impl std::str::FromStr for GestureBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (gesture, command) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected GESTURE=COMMAND, got \"{s}\""))?;
+        Ok(Self {
+            gesture: gesture.parse()?,
+            command: Command::deserialize(command.trim().as_bytes())
+                .map_err(|e| format!("{e}: \"{command}\""))?,
+        })
+    }
+}
+
+#[derive(Subcommand, Clone)]
 pub enum Command {
     Toggle,
+    /// Take input until deactivated, or only for a while
+    Activate {
+        /// Go back to passing input through after drawing one line
+        #[arg(long)]
+        once: bool,
+        /// Go back to passing input through after this many seconds without drawing
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<f32>,
+    },
+    /// Go back to passing input through
+    Deactivate,
     Undo,
+    /// Bring back the last undone line
+    Redo,
     Clear,
     ClearAndDeactivate,
-    StrokeWidth { width: f32 },
-    StrokeColor { color: csscolorparser::Color },
+    StrokeWidth {
+        width: f32,
+    },
+    StrokeColor {
+        color: csscolorparser::Color,
+    },
+    /// Switch to the next color of the palette
+    NextColor,
+    /// Switch to the previous color of the palette
+    PreviousColor,
+    /// Switch to a color of the palette, counting from 1
+    Color {
+        index: usize,
+    },
+    /// Map a pointer or stylus button to a tool with stroke settings
+    ///
+    /// Buttons can be given by name (left, right, middle, side, extra, tip, stylus, stylus2,
+    /// stylus3) or by their evdev code. Presets are written as [TOOL][:WIDTH[:COLOR]], e.g.
+    /// `eraser` or `pen:8:red`, and draw with the pen if they leave out the tool.
+    Bind {
+        button: Button,
+        preset: Preset,
+    },
+    /// Assign a preset to the physical stylus that was last in proximity
+    ///
+    /// Presets are written as [TOOL][:WIDTH[:COLOR]], e.g. `pen:4:red` or `::blue`.
+    Stylus {
+        preset: Preset,
+    },
+    /// Move the overlay to another output
+    ///
+    /// The destination is an output name or part of its description, `next` for the following
+    /// output or `focused` to let the compositor pick.
+    MoveToOutput {
+        destination: Destination,
+        /// What happens to the lines already drawn
+        #[arg(long, value_enum, default_value_t = StrokeTransfer::Keep)]
+        strokes: StrokeTransfer,
+    },
+    /// Stop showing the lines without losing them, input passes through meanwhile
+    Hide,
+    /// Show the lines again after hiding them
+    Show,
+    /// Hide the lines, or show them if they are hidden
+    ToggleVisibility,
+    /// Show the toolbar at a screen edge, or without an edge toggle it
+    Toolbar {
+        #[arg(value_enum)]
+        edge: Option<Edge>,
+    },
     Exit,
 }
 
@@ -15,7 +440,19 @@ impl Command {
     pub fn serialize(&self) -> Vec<u8> {
         match self {
             Command::Toggle => b"toggle".to_vec(),
+            Command::Activate { once, timeout } => {
+                let mut s = "activate".to_string();
+                if *once {
+                    s.push_str(" once");
+                }
+                if let Some(timeout) = timeout {
+                    s.push_str(&format!(" timeout {}", timeout));
+                }
+                s.as_bytes().to_vec()
+            }
+            Command::Deactivate => b"deactivate".to_vec(),
             Command::Undo => b"undo".to_vec(),
+            Command::Redo => b"redo".to_vec(),
             Command::Clear => b"clear".to_vec(),
             Command::ClearAndDeactivate => b"clear_and_deactivate".to_vec(),
             Command::StrokeWidth { width } => {
@@ -26,6 +463,35 @@ impl Command {
                 let s = format!("stroke_color {}", color.to_css_hex());
                 s.as_bytes().to_vec()
             }
+            Command::NextColor => b"next_color".to_vec(),
+            Command::PreviousColor => b"previous_color".to_vec(),
+            Command::Color { index } => {
+                let s = format!("color {}", index);
+                s.as_bytes().to_vec()
+            }
+            Command::Bind { button, preset } => {
+                let s = format!("bind {} {}", button.0, preset);
+                s.as_bytes().to_vec()
+            }
+            Command::Stylus { preset } => {
+                let s = format!("stylus {}", preset);
+                s.as_bytes().to_vec()
+            }
+            Command::MoveToOutput {
+                destination,
+                strokes,
+            } => {
+                let s = format!("move_to_output {} {}", destination, strokes.name());
+                s.as_bytes().to_vec()
+            }
+            Command::Hide => b"hide".to_vec(),
+            Command::Show => b"show".to_vec(),
+            Command::ToggleVisibility => b"toggle_visibility".to_vec(),
+            Command::Toolbar { edge: None } => b"toolbar".to_vec(),
+            Command::Toolbar { edge: Some(edge) } => {
+                let s = format!("toolbar {}", edge.name());
+                s.as_bytes().to_vec()
+            }
             Command::Exit => b"exit".to_vec(),
         }
     }
@@ -35,7 +501,31 @@ impl Command {
 
         match split.next() {
             Some(b"toggle") => Ok(Self::Toggle),
+            Some(b"activate") => {
+                let mut once = false;
+                let mut timeout = None;
+                while let Some(option) = split.next() {
+                    match option {
+                        b"once" => once = true,
+                        b"timeout" => {
+                            timeout = split
+                                .next()
+                                .and_then(|timeout_text| std::str::from_utf8(timeout_text).ok())
+                                .and_then(|timeout_text| timeout_text.parse::<f32>().ok());
+                            if timeout.is_none() {
+                                return Err(
+                                    "received activate message but couldn't parse a timeout",
+                                );
+                            }
+                        }
+                        _ => return Err("received activate message with an unknown option"),
+                    }
+                }
+                Ok(Self::Activate { once, timeout })
+            }
+            Some(b"deactivate") => Ok(Self::Deactivate),
             Some(b"undo") => Ok(Self::Undo),
+            Some(b"redo") => Ok(Self::Redo),
             Some(b"clear") => Ok(Self::Clear),
             Some(b"clear_and_deactivate") => Ok(Self::ClearAndDeactivate),
             Some(b"stroke_width") => {
@@ -44,7 +534,7 @@ impl Command {
                     .and_then(|width_text| String::from_utf8(width_text.to_vec()).ok())
                     .and_then(|width_text| width_text.parse::<f32>().ok())
                 {
-                    Some(width) => Ok(Self::StrokeWidth { width: width }),
+                    Some(width) => Ok(Self::StrokeWidth { width }),
                     None => Err("received stroke width message but couldn't parse a width"),
                 }
             }
@@ -54,13 +544,526 @@ impl Command {
                     .and_then(|color_text| String::from_utf8(color_text.to_vec()).ok())
                     .and_then(|color_text| csscolorparser::parse(&color_text).ok())
                 {
-                    Some(color) => Ok(Self::StrokeColor { color: color }),
+                    Some(color) => Ok(Self::StrokeColor { color }),
                     None => Err("received stroke color message but couldn't parse a color"),
                 }
             }
+            Some(b"next_color") => Ok(Self::NextColor),
+            Some(b"previous_color") => Ok(Self::PreviousColor),
+            Some(b"color") => {
+                match split
+                    .next()
+                    .and_then(|index_text| std::str::from_utf8(index_text).ok())
+                    .and_then(|index_text| index_text.parse::<usize>().ok())
+                {
+                    Some(index) => Ok(Self::Color { index }),
+                    None => Err("received color message but couldn't parse an index"),
+                }
+            }
+            Some(b"bind") => {
+                let button = split
+                    .next()
+                    .and_then(|button_text| std::str::from_utf8(button_text).ok())
+                    .and_then(|button_text| button_text.parse::<Button>().ok());
+                let preset = split
+                    .next()
+                    .and_then(|preset_text| std::str::from_utf8(preset_text).ok())
+                    .and_then(|preset_text| preset_text.parse::<Preset>().ok());
+                match (button, preset) {
+                    (Some(button), Some(preset)) => Ok(Self::Bind { button, preset }),
+                    _ => Err("received bind message but couldn't parse a button and preset"),
+                }
+            }
+            Some(b"stylus") => {
+                match split
+                    .next()
+                    .and_then(|preset_text| std::str::from_utf8(preset_text).ok())
+                    .and_then(|preset_text| preset_text.parse::<Preset>().ok())
+                {
+                    Some(preset) => Ok(Self::Stylus { preset }),
+                    None => Err("received stylus message but couldn't parse a preset"),
+                }
+            }
+            Some(b"move_to_output") => {
+                // quoted destinations may contain spaces
+                let (destination_text, strokes_text) = s
+                    .strip_prefix(b"move_to_output ")
+                    .and_then(split_output)
+                    .unwrap_or_default();
+                let destination = std::str::from_utf8(destination_text)
+                    .ok()
+                    .and_then(|destination_text| destination_text.parse::<Destination>().ok());
+                let strokes = (!strokes_text.is_empty())
+                    .then(|| std::str::from_utf8(strokes_text).ok())
+                    .flatten()
+                    .map(|strokes_text| StrokeTransfer::from_str(strokes_text, true));
+                match (destination, strokes) {
+                    (Some(destination), None) => Ok(Self::MoveToOutput {
+                        destination,
+                        strokes: StrokeTransfer::Keep,
+                    }),
+                    (Some(destination), Some(Ok(strokes))) => Ok(Self::MoveToOutput {
+                        destination,
+                        strokes,
+                    }),
+                    _ => Err("received move to output message but couldn't parse a destination"),
+                }
+            }
+            Some(b"hide") => Ok(Self::Hide),
+            Some(b"show") => Ok(Self::Show),
+            Some(b"toggle_visibility") => Ok(Self::ToggleVisibility),
+            Some(b"toolbar") => {
+                match split.next().map(|edge_text| {
+                    std::str::from_utf8(edge_text)
+                        .ok()
+                        .and_then(|edge_text| Edge::from_str(edge_text, true).ok())
+                }) {
+                    None => Ok(Self::Toolbar { edge: None }),
+                    Some(Some(edge)) => Ok(Self::Toolbar { edge: Some(edge) }),
+                    Some(None) => Err("received toolbar message but couldn't parse an edge"),
+                }
+            }
             Some(b"exit") => Ok(Self::Exit),
             Some(_message) => Err("unknown message"),
             None => Err("received empty message"),
         }
     }
 }
+
+/// The outputs a command applies to
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    All,
+    /// a connector name (e.g. `DP-1`) or part of an output's description
+    Output(String),
+}
+
+impl Target {
+    const KEYWORDS: [&'static str; 1] = ["all"];
+}
+
+impl std::str::FromStr for Target {
+    type Err = String;
+
+    /// Takes `all`, or an output the way `chameleos --output` does, in double quotes if it
+    /// happens to be called `all`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("expected an output name or `all`".to_string()),
+            "all" => Ok(Self::All),
+            s => Ok(Self::Output(unquote_output(s)?.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::All => write!(f, "all"),
+            Target::Output(name) => write_output(f, name, &Self::KEYWORDS),
+        }
+    }
+}
+
+/// Strips the double quotes around an output name or description
+fn unquote_output(s: &str) -> Result<&str, String> {
+    let unquoted = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s);
+    if unquoted.is_empty() || unquoted.contains('"') {
+        return Err(format!("couldn't parse output \"{s}\""));
+    }
+    Ok(unquoted)
+}
+
+/// Writes an output name or description, in double quotes if it would otherwise be cut at a
+/// space or taken for a keyword
+fn write_output(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    keywords: &[&str],
+) -> std::fmt::Result {
+    if name.contains(' ') || keywords.contains(&name) {
+        write!(f, "\"{name}\"")
+    } else {
+        write!(f, "{name}")
+    }
+}
+
+/// Splits an output name or description at the start of a message from whatever follows it
+fn split_output(s: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = match s.strip_prefix(b"\"") {
+        Some(quoted) => quoted.iter().position(|&c| c == b'"')? + 2,
+        None => s.iter().position(|&c| c == b' ').unwrap_or(s.len()),
+    };
+    match s.split_at(end) {
+        (output, []) => Some((output, &[])),
+        (output, [b' ', rest @ ..]) => Some((output, rest)),
+        _ => None,
+    }
+}
+
+/// Where `move-to-output` sends an overlay
+#[derive(Clone, Debug, PartialEq)]
+pub enum Destination {
+    /// the output after the current one
+    Next,
+    /// whichever output the compositor considers focused
+    Focused,
+    /// a connector name (e.g. `DP-1`) or part of an output's description
+    Output(String),
+}
+
+impl Destination {
+    const KEYWORDS: [&'static str; 2] = ["next", "focused"];
+}
+
+impl std::str::FromStr for Destination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("expected an output name, `next` or `focused`".to_string()),
+            "next" => Ok(Self::Next),
+            "focused" => Ok(Self::Focused),
+            s => Ok(Self::Output(unquote_output(s)?.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Destination::Next => write!(f, "next"),
+            Destination::Focused => write!(f, "focused"),
+            Destination::Output(name) => write_output(f, name, &Self::KEYWORDS),
+        }
+    }
+}
+
+/// What happens to the lines of an overlay that moves to another output
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum StrokeTransfer {
+    /// keep the lines where they are, relative to the top left corner
+    #[default]
+    Keep,
+    /// stretch the lines to the size of the new output
+    Rescale,
+    /// leave the lines behind, they come back when moving back to the output
+    Stash,
+}
+
+impl StrokeTransfer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StrokeTransfer::Keep => "keep",
+            StrokeTransfer::Rescale => "rescale",
+            StrokeTransfer::Stash => "stash",
+        }
+    }
+}
+
+/// The screen edge the toolbar is docked to
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Edge {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Edge::Top => "top",
+            Edge::Bottom => "bottom",
+            Edge::Left => "left",
+            Edge::Right => "right",
+        }
+    }
+}
+
+/// A command as sent over the socket, optionally prefixed with `on TARGET`
+pub struct Message {
+    pub target: Option<Target>,
+    pub command: Command,
+}
+
+impl Message {
+    pub fn serialize(&self) -> Vec<u8> {
+        match self.target {
+            Some(ref target) => {
+                let mut s = format!("on {target} ").into_bytes();
+                s.extend(self.command.serialize());
+                s
+            }
+            None => self.command.serialize(),
+        }
+    }
+
+    pub fn deserialize(s: &[u8]) -> Result<Self, &'static str> {
+        match s.strip_prefix(b"on ") {
+            Some(rest) => {
+                let (target, command) = split_output(rest)
+                    .filter(|(_, command)| !command.is_empty())
+                    .ok_or("received message for an output but no command")?;
+                let target = std::str::from_utf8(target)
+                    .ok()
+                    .and_then(|target| target.parse::<Target>().ok())
+                    .ok_or("received message for an output but couldn't parse the output")?;
+                Ok(Self {
+                    target: Some(target),
+                    command: Command::deserialize(command)?,
+                })
+            }
+            None => Ok(Self {
+                target: None,
+                command: Command::deserialize(s)?,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(s: &str) -> csscolorparser::Color {
+        csscolorparser::parse(s).unwrap()
+    }
+
+    /// Commands should come out of the socket the way they went in
+    fn assert_round_trip(commands: &[&str]) {
+        for command in commands {
+            let deserialized = Command::deserialize(command.as_bytes()).unwrap();
+            assert_eq!(
+                String::from_utf8(deserialized.serialize()).unwrap(),
+                *command
+            );
+        }
+    }
+
+    #[test]
+    fn parses_buttons() {
+        assert_eq!("middle".parse(), Ok(Button::MIDDLE));
+        assert_eq!(" Stylus2 ".parse(), Ok(Button::STYLUS2));
+        assert_eq!("330".parse(), Ok(Button::TIP));
+        assert_eq!("999".parse(), Ok(Button(999)));
+        assert!("wheel".parse::<Button>().is_err());
+
+        assert_eq!(Button::STYLUS3.to_string(), "stylus3");
+        assert_eq!(Button(999).to_string(), "999");
+    }
+
+    #[test]
+    fn parses_presets() {
+        assert_eq!(
+            "pen:4:red".parse(),
+            Ok(Preset {
+                tool: Some(Tool::Pen),
+                width: Some(4.0),
+                color: Some(color("red")),
+            })
+        );
+        assert_eq!(
+            "::blue".parse(),
+            Ok(Preset {
+                color: Some(color("blue")),
+                ..Default::default()
+            })
+        );
+        assert_eq!("".parse(), Ok(Preset::default()));
+        assert!("brush".parse::<Preset>().is_err());
+        assert!("pen:thick".parse::<Preset>().is_err());
+        assert!("pen:4:nocolor".parse::<Preset>().is_err());
+
+        for preset in [
+            "pen:4:#ff0000",
+            "eraser",
+            "laser::#ff0000",
+            "ellipse:2",
+            ":2.5",
+            "",
+        ] {
+            assert_eq!(preset.parse::<Preset>().unwrap().to_string(), preset);
+        }
+    }
+
+    #[test]
+    fn presets_fall_back_to_each_other() {
+        let pen: Preset = "pen:4".parse().unwrap();
+        let eraser: Preset = "eraser:8:red".parse().unwrap();
+        assert_eq!(pen.or(&eraser), "pen:4:red".parse().unwrap());
+    }
+
+    #[test]
+    fn parses_bindings() {
+        let binding: Binding = "stylus2=eraser".parse().unwrap();
+        assert_eq!(binding.button, Button::STYLUS2);
+        assert_eq!(binding.preset, "eraser".parse().unwrap());
+
+        let binding: Binding = "side=pen:8:red".parse().unwrap();
+        assert_eq!(binding.button, Button::SIDE);
+        assert_eq!(binding.preset, "pen:8:red".parse().unwrap());
+
+        assert!("middle".parse::<Binding>().is_err());
+        assert!("wheel=pen".parse::<Binding>().is_err());
+        assert!("middle=brush".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn commands_survive_the_socket() {
+        assert_round_trip(&[
+            "toggle",
+            "undo",
+            "clear",
+            "clear_and_deactivate",
+            "stroke_width 16",
+            "stroke_color #00bfff",
+            "bind 274 eraser",
+            "bind 275 pen:8:#ff0000",
+            "exit",
+        ]);
+
+        assert!(Command::deserialize(b"stroke_width thick").is_err());
+        assert!(Command::deserialize(b"bind wheel pen").is_err());
+        assert!(Command::deserialize(b"dance").is_err());
+        assert!(Command::deserialize(b"").is_err());
+    }
+
+    #[test]
+    fn stylus_presets_survive_the_socket() {
+        assert_round_trip(&["stylus ::#0000ff", "stylus pen:4"]);
+        assert!(Command::deserialize(b"stylus brush").is_err());
+    }
+
+    #[test]
+    fn palette_commands_survive_the_socket() {
+        assert_round_trip(&["next_color", "previous_color"]);
+    }
+
+    #[test]
+    fn parses_gesture_bindings() {
+        let binding: GestureBinding = "swipe-left:3=undo".parse().unwrap();
+        assert_eq!(
+            binding.gesture,
+            Gesture {
+                kind: GestureKind::SwipeLeft,
+                fingers: Some(3),
+            }
+        );
+        assert_eq!(binding.command.serialize(), b"undo");
+
+        let binding: GestureBinding = "pinch-out=stroke_width 16".parse().unwrap();
+        assert_eq!(
+            binding.gesture,
+            Gesture {
+                kind: GestureKind::PinchOut,
+                fingers: None,
+            }
+        );
+        assert_eq!(binding.command.serialize(), b"stroke_width 16");
+
+        assert!("swipe-left:3".parse::<GestureBinding>().is_err());
+        assert!("swipe-left:three=undo".parse::<GestureBinding>().is_err());
+        assert!("wave=undo".parse::<GestureBinding>().is_err());
+        assert!("hold=nonsense".parse::<GestureBinding>().is_err());
+    }
+
+    #[test]
+    fn redo_survives_the_socket() {
+        assert_round_trip(&["redo"]);
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!("all".parse(), Ok(Target::All));
+        assert_eq!("DP-1".parse(), Ok(Target::Output("DP-1".to_string())));
+        assert_eq!(
+            "Dell U2720Q".parse(),
+            Ok(Target::Output("Dell U2720Q".to_string()))
+        );
+        assert_eq!("\"all\"".parse(), Ok(Target::Output("all".to_string())));
+        assert!("".parse::<Target>().is_err());
+        assert!("\"\"".parse::<Target>().is_err());
+        assert!("Dell \"U2720Q\"".parse::<Target>().is_err());
+
+        assert_eq!(Target::Output("all".to_string()).to_string(), "\"all\"");
+        assert_eq!(
+            Target::Output("Dell U2720Q".to_string()).to_string(),
+            "\"Dell U2720Q\""
+        );
+    }
+
+    #[test]
+    fn messages_survive_the_socket() {
+        for message in [
+            "undo",
+            "on DP-1 clear",
+            "on all stroke_width 16",
+            "on \"Dell U2720Q\" undo",
+            "on \"all\" clear",
+        ] {
+            let deserialized = Message::deserialize(message.as_bytes()).unwrap();
+            assert_eq!(
+                String::from_utf8(deserialized.serialize()).unwrap(),
+                message
+            );
+        }
+
+        let message = Message::deserialize(b"on \"Dell U2720Q\" undo").unwrap();
+        assert_eq!(
+            message.target,
+            Some(Target::Output("Dell U2720Q".to_string()))
+        );
+
+        assert!(Message::deserialize(b"on DP-1").is_err());
+        assert!(Message::deserialize(b"on DP-1 ").is_err());
+        assert!(Message::deserialize(b"on \"Dell U2720Q undo").is_err());
+    }
+
+    #[test]
+    fn move_to_output_survives_the_socket() {
+        assert_round_trip(&[
+            "move_to_output next keep",
+            "move_to_output focused rescale",
+            "move_to_output \"Dell U2720Q\" stash",
+            "move_to_output \"next\" keep",
+        ]);
+        assert!(Command::deserialize(b"move_to_output").is_err());
+        assert!(Command::deserialize(b"move_to_output next throw").is_err());
+    }
+
+    #[test]
+    fn color_survives_the_socket() {
+        assert_round_trip(&["color 3"]);
+        assert!(Command::deserialize(b"color red").is_err());
+    }
+
+    #[test]
+    fn toolbar_survives_the_socket() {
+        assert_round_trip(&["toolbar", "toolbar left"]);
+        assert!(Command::deserialize(b"toolbar middle").is_err());
+    }
+
+    #[test]
+    fn activation_survives_the_socket() {
+        assert_round_trip(&[
+            "activate",
+            "activate once",
+            "activate once timeout 30",
+            "activate timeout 2.5",
+            "deactivate",
+        ]);
+        assert!(Command::deserialize(b"activate timeout").is_err());
+        assert!(Command::deserialize(b"activate twice").is_err());
+    }
+
+    #[test]
+    fn visibility_survives_the_socket() {
+        assert_round_trip(&["hide", "show", "toggle_visibility"]);
+    }
+}